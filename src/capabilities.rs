@@ -1,14 +1,23 @@
 use std::fmt::{Display, Formatter};
 
 use lsp_types::{
-    CodeActionKind, CodeActionOptions, CodeActionProviderCapability, CompletionOptions,
-    CompletionOptionsCompletionItem, DiagnosticOptions, DiagnosticServerCapabilities, HoverOptions,
-    HoverProviderCapability, NumberOrString, OneOf, SemanticTokenModifier, SemanticTokenType,
-    SemanticTokensFullOptions, SemanticTokensLegend, SemanticTokensOptions,
-    SemanticTokensServerCapabilities, ServerCapabilities, TextDocumentSyncCapability,
-    TextDocumentSyncKind, WorkDoneProgressOptions,
+    CallHierarchyServerCapability, CodeActionKind, CodeActionOptions, CodeActionProviderCapability,
+    CodeLensOptions, CompletionOptions, CompletionOptionsCompletionItem, DiagnosticOptions,
+    DiagnosticServerCapabilities, DocumentLinkOptions, DocumentOnTypeFormattingOptions,
+    ExecuteCommandOptions, FoldingRangeProviderCapability, HoverOptions, HoverProviderCapability,
+    LinkedEditingRangeServerCapabilities, NumberOrString, OneOf, SelectionRangeProviderCapability,
+    SemanticTokenModifier, SemanticTokenType, SemanticTokensFullOptions, SemanticTokensLegend,
+    SemanticTokensOptions, SemanticTokensServerCapabilities, ServerCapabilities,
+    TextDocumentSyncCapability, TextDocumentSyncKind, WorkDoneProgressOptions,
 };
 
+use crate::command;
+
+/**
+ * `<` and `:` start tag names, a space starts the next attribute.
+ */
+const COMPLETION_TRIGGER_CHARACTERS: [&str; 3] = ["<", ":", " "];
+
 pub(crate) const TOKEN_TYPES: &'static [SemanticTokenType] = &[
     SemanticTokenType::ENUM,
     SemanticTokenType::ENUM_MEMBER,
@@ -37,11 +46,23 @@ pub(crate) enum CodeActionImplementation {
     NameToCondition,
     ConditionToName,
     FixSpelSyntax,
+    WrapInIf,
+    RemoveDeprecatedTag,
+    RemoveUnusedTaglib,
+    AddMissingTaglib,
+    ExtractVariable,
+    SortAttributes,
+    AddContentType,
+    FormatSpel,
 }
 
 impl CodeActionImplementation {
     pub(crate) const GENERATE_DEFAULT_HEADER_CODE: NumberOrString = NumberOrString::Number(7126);
     pub(crate) const FIX_SPEL_SYNTAX_CODE: NumberOrString = NumberOrString::Number(7127);
+    pub(crate) const REMOVE_DEPRECATED_TAG_CODE: NumberOrString = NumberOrString::Number(7128);
+    pub(crate) const REMOVE_UNUSED_TAGLIB_CODE: NumberOrString = NumberOrString::Number(7129);
+    pub(crate) const ADD_MISSING_TAGLIB_CODE: NumberOrString = NumberOrString::Number(7130);
+    pub(crate) const ADD_CONTENT_TYPE_CODE: NumberOrString = NumberOrString::Number(7131);
 
     pub(crate) fn kinds() -> Vec<CodeActionKind> {
         return vec![
@@ -49,6 +70,14 @@ impl CodeActionImplementation {
             CodeActionImplementation::NameToCondition.to_kind(),
             CodeActionImplementation::ConditionToName.to_kind(),
             CodeActionImplementation::FixSpelSyntax.to_kind(),
+            CodeActionImplementation::WrapInIf.to_kind(),
+            CodeActionImplementation::RemoveDeprecatedTag.to_kind(),
+            CodeActionImplementation::RemoveUnusedTaglib.to_kind(),
+            CodeActionImplementation::AddMissingTaglib.to_kind(),
+            CodeActionImplementation::ExtractVariable.to_kind(),
+            CodeActionImplementation::SortAttributes.to_kind(),
+            CodeActionImplementation::AddContentType.to_kind(),
+            CodeActionImplementation::FormatSpel.to_kind(),
             CodeActionKind::SOURCE_FIX_ALL,
         ];
     }
@@ -59,6 +88,14 @@ impl CodeActionImplementation {
             CodeActionImplementation::NameToCondition => "refactor.name_to_condition",
             CodeActionImplementation::ConditionToName => "refactor.condition_to_name",
             CodeActionImplementation::FixSpelSyntax => "quickfix.fix_spel_syntax",
+            CodeActionImplementation::WrapInIf => "refactor.wrap_in_if",
+            CodeActionImplementation::RemoveDeprecatedTag => "quickfix.remove_deprecated_tag",
+            CodeActionImplementation::RemoveUnusedTaglib => "quickfix.remove_unused_taglib",
+            CodeActionImplementation::AddMissingTaglib => "quickfix.add_missing_taglib",
+            CodeActionImplementation::ExtractVariable => "refactor.extract_variable",
+            CodeActionImplementation::SortAttributes => "refactor.sort_attributes",
+            CodeActionImplementation::AddContentType => "quickfix.add_content_type",
+            CodeActionImplementation::FormatSpel => "refactor.format_spel",
         });
     }
 }
@@ -70,6 +107,14 @@ impl Display for CodeActionImplementation {
             CodeActionImplementation::NameToCondition => "refactor.name_to_condition",
             CodeActionImplementation::ConditionToName => "refactor.condition_to_name",
             CodeActionImplementation::FixSpelSyntax => "quickfix.fix_spel_syntax",
+            CodeActionImplementation::WrapInIf => "refactor.wrap_in_if",
+            CodeActionImplementation::RemoveDeprecatedTag => "quickfix.remove_deprecated_tag",
+            CodeActionImplementation::RemoveUnusedTaglib => "quickfix.remove_unused_taglib",
+            CodeActionImplementation::AddMissingTaglib => "quickfix.add_missing_taglib",
+            CodeActionImplementation::ExtractVariable => "refactor.extract_variable",
+            CodeActionImplementation::SortAttributes => "refactor.sort_attributes",
+            CodeActionImplementation::AddContentType => "quickfix.add_content_type",
+            CodeActionImplementation::FormatSpel => "refactor.format_spel",
         })
     }
 }
@@ -79,6 +124,7 @@ pub(crate) fn create() -> ServerCapabilities {
         definition_provider: Some(OneOf::Left(true)),
         text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
         document_highlight_provider: Some(OneOf::Left(true)),
+        folding_range_provider: Some(FoldingRangeProviderCapability::Simple(true)),
         semantic_tokens_provider: Some(SemanticTokensServerCapabilities::SemanticTokensOptions(
             SemanticTokensOptions {
                 full: Some(SemanticTokensFullOptions::Bool(true)),
@@ -94,6 +140,7 @@ pub(crate) fn create() -> ServerCapabilities {
             ..DiagnosticOptions::default()
         })),
         completion_provider: Some(CompletionOptions {
+            trigger_characters: Some(COMPLETION_TRIGGER_CHARACTERS.map(String::from).to_vec()),
             completion_item: Some(CompletionOptionsCompletionItem {
                 label_details_support: Some(true),
             }),
@@ -108,6 +155,39 @@ pub(crate) fn create() -> ServerCapabilities {
             code_action_kinds: Some(CodeActionImplementation::kinds()),
             ..CodeActionOptions::default()
         })),
+        selection_range_provider: Some(SelectionRangeProviderCapability::Simple(true)),
+        document_symbol_provider: Some(OneOf::Left(true)),
+        workspace_symbol_provider: Some(OneOf::Left(true)),
+        code_lens_provider: Some(CodeLensOptions {
+            resolve_provider: Some(false),
+        }),
+        call_hierarchy_provider: Some(CallHierarchyServerCapability::Simple(true)),
+        document_link_provider: Some(DocumentLinkOptions {
+            resolve_provider: Some(false),
+            work_done_progress_options: WorkDoneProgressOptions::default(),
+        }),
+        document_on_type_formatting_provider: Some(DocumentOnTypeFormattingOptions {
+            first_trigger_character: ">".to_string(),
+            more_trigger_character: None,
+        }),
+        linked_editing_range_provider: Some(LinkedEditingRangeServerCapabilities::Simple(true)),
+        execute_command_provider: Some(ExecuteCommandOptions {
+            commands: command::commands(),
+            work_done_progress_options: WorkDoneProgressOptions::default(),
+        }),
         ..ServerCapabilities::default()
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::create;
+
+    #[test]
+    fn test_completion_trigger_characters() {
+        assert_eq!(
+            create().completion_provider.unwrap().trigger_characters,
+            Some(vec!["<".to_string(), ":".to_string(), " ".to_string()])
+        );
+    }
+}
@@ -2,27 +2,43 @@ use std::collections::HashMap;
 
 use lsp_server::ErrorCode;
 use lsp_types::{
-    CodeAction, CodeActionKind, CodeActionOrCommand, CodeActionParams, Position, Range, TextEdit,
-    Url, WorkspaceEdit,
+    CodeAction, CodeActionKind, CodeActionOrCommand, CodeActionParams, Diagnostic,
+    DiagnosticSeverity, Position, Range, TextEdit, Url, WorkspaceEdit,
 };
 use tree_sitter::{Node, Point};
 
 use crate::{
     capabilities::CodeActionImplementation,
+    config::{self, Config},
     document_store::{self, Document},
-    spel::ast::{
-        Argument, Comparable, ComparissonOperator, Condition, Function, SpelAst, SpelResult,
+    grammar, parser,
+    spel::{
+        ast::{
+            Argument, Comparable, ComparissonOperator, Condition, Function, SpelAst, SpelResult,
+        },
+        format,
     },
 };
 
-use super::LsError;
+use super::{selection, LsError};
 
-const DEFAULT_HEADER: &str = concat!(
-    "<%@ page language=\"java\" pageEncoding=\"UTF-8\" contentType=\"text/html; charset=UTF-8\"\n",
-    "%><%@ taglib uri=\"http://www.sitepark.com/taglibs/core\" prefix=\"sp\"\n",
-    "%><%@ taglib tagdir=\"/WEB-INF/tags/spt\" prefix=\"spt\"\n",
-    "%>\n"
-);
+/**
+ * the page header and taglib imports inserted by the "generate default header" action.
+ */
+fn default_header(config: &Config) -> String {
+    let mut header = format!(
+        "<%@ page language=\"java\" pageEncoding=\"{}\" contentType=\"{}\"\n%>",
+        config.page_encoding, config.content_type
+    );
+    for prefix in &config.default_taglibs {
+        match grammar::taglib_import(prefix) {
+            Some(import) => header.push_str(import),
+            None => log::info!("cannot generate import for unknown taglib \"{}\"", prefix),
+        }
+    }
+    header.push('\n');
+    return header;
+}
 
 pub(crate) fn action(params: CodeActionParams) -> Result<Vec<CodeActionOrCommand>, LsError> {
     let uri = params.text_document.uri;
@@ -44,22 +60,10 @@ pub(crate) fn action(params: CodeActionParams) -> Result<Vec<CodeActionOrCommand
         .only
         .is_some_and(|kinds| kinds.contains(&CodeActionKind::SOURCE_FIX_ALL))
     {
-        let edits = params
-            .context
-            .diagnostics
-            .iter()
-            .filter_map(|diagnostic| match diagnostic.code {
-                Some(CodeActionImplementation::FIX_SPEL_SYNTAX_CODE) => diagnostic
-                    .data
-                    .as_ref()
-                    .and_then(|data| serde_json::from_value::<Vec<TextEdit>>(data.to_owned()).ok()),
-                _ => None,
-            })
-            .flatten()
-            .collect::<Vec<TextEdit>>();
+        let edits = collect_fix_all_edits(&params.context.diagnostics);
         if edits.len() > 0 {
             actions.push(CodeActionOrCommand::CodeAction(CodeAction {
-                title: "fix all spel syntax errors".to_string(),
+                title: "fix all".to_string(),
                 kind: Some(CodeActionKind::SOURCE_FIX_ALL),
                 edit: Some(WorkspaceEdit {
                     changes: Some(HashMap::from([(uri.clone(), edits)])),
@@ -75,16 +79,48 @@ pub(crate) fn action(params: CodeActionParams) -> Result<Vec<CodeActionOrCommand
                     actions.push(construct_generate_default_header(&uri))
                 }
                 Some(CodeActionImplementation::FIX_SPEL_SYNTAX_CODE) => {
-                    diagnostic
+                    if let Some(edits) = diagnostic
+                        .data
+                        .and_then(|data| serde_json::from_value(data).ok())
+                    {
+                        actions.push(construct_fix_spel_syntax(
+                            &uri,
+                            format!("quick-fix: {}", diagnostic.message),
+                            edits,
+                        ));
+                    }
+                }
+                Some(CodeActionImplementation::REMOVE_DEPRECATED_TAG_CODE) => {
+                    if let Some(edits) = diagnostic
+                        .data
+                        .and_then(|data| serde_json::from_value(data).ok())
+                    {
+                        actions.push(construct_remove_deprecated_tag(&uri, edits));
+                    }
+                }
+                Some(CodeActionImplementation::REMOVE_UNUSED_TAGLIB_CODE) => {
+                    if let Some(edits) = diagnostic
+                        .data
+                        .and_then(|data| serde_json::from_value(data).ok())
+                    {
+                        actions.push(construct_remove_unused_taglib(&uri, edits));
+                    }
+                }
+                Some(CodeActionImplementation::ADD_MISSING_TAGLIB_CODE) => {
+                    if let Some(edits) = diagnostic
+                        .data
+                        .and_then(|data| serde_json::from_value(data).ok())
+                    {
+                        actions.push(construct_add_missing_taglib(&uri, edits));
+                    }
+                }
+                Some(CodeActionImplementation::ADD_CONTENT_TYPE_CODE) => {
+                    if let Some(edits) = diagnostic
                         .data
                         .and_then(|data| serde_json::from_value(data).ok())
-                        .map(|edits| {
-                            actions.push(construct_fix_spel_syntax(
-                                &uri,
-                                format!("quick-fix: {}", diagnostic.message),
-                                edits,
-                            ))
-                        });
+                    {
+                        actions.push(construct_add_content_type(&uri, edits));
+                    }
                 }
                 _ => (),
             }
@@ -115,24 +151,120 @@ pub(crate) fn action(params: CodeActionParams) -> Result<Vec<CodeActionOrCommand
         },
         None => {}
     };
+    if let Some(action) = construct_wrap_in_if(&document, &uri, &params.range) {
+        actions.push(action);
+    }
+    if let Some(action) = construct_extract_variable(&document, &uri, params.range.start) {
+        actions.push(action);
+    }
+    if let Some(action) = construct_sort_attributes(&document, &uri, params.range.start) {
+        actions.push(action);
+    }
+    if let Some(action) = construct_format_spel(&document, &uri, params.range.start) {
+        actions.push(action);
+    }
     return Ok(actions);
 }
 
+/**
+ * combines the edits of all quick-fixes for the given diagnostics. when the edits of two
+ * diagnostics overlap only the fix of the more severe one is kept.
+ * removing a deprecated tag also removes its body, so it is only offered as a single quick-fix.
+ */
+fn collect_fix_all_edits(diagnostics: &[Diagnostic]) -> Vec<TextEdit> {
+    let mut fixes = diagnostics
+        .iter()
+        .filter_map(|diagnostic| match diagnostic.code {
+            Some(CodeActionImplementation::FIX_SPEL_SYNTAX_CODE)
+            | Some(CodeActionImplementation::REMOVE_UNUSED_TAGLIB_CODE)
+            | Some(CodeActionImplementation::ADD_MISSING_TAGLIB_CODE)
+            | Some(CodeActionImplementation::ADD_CONTENT_TYPE_CODE) => diagnostic
+                .data
+                .as_ref()
+                .and_then(|data| serde_json::from_value::<Vec<TextEdit>>(data.to_owned()).ok())
+                .map(|edits| (severity_rank(diagnostic.severity), edits)),
+            _ => None,
+        })
+        .collect::<Vec<(u8, Vec<TextEdit>)>>();
+    fixes.sort_by_key(|(rank, _)| *rank);
+    let mut edits: Vec<TextEdit> = Vec::new();
+    for (_, fix) in fixes {
+        if !fix.iter().any(|edit| {
+            edits
+                .iter()
+                .any(|accepted| ranges_overlap(&edit.range, &accepted.range))
+        }) {
+            edits.extend(fix);
+        }
+    }
+    return edits;
+}
+
+/**
+ * combines the proposed fixes of all spel syntax errors. when two fixes overlap the one starting
+ * earlier in the document is kept.
+ */
+pub(super) fn collect_spel_syntax_edits(diagnostics: &[Diagnostic]) -> Vec<TextEdit> {
+    let mut fixes = diagnostics
+        .iter()
+        .filter(|diagnostic| {
+            diagnostic.code == Some(CodeActionImplementation::FIX_SPEL_SYNTAX_CODE)
+        })
+        .filter_map(|diagnostic| {
+            diagnostic
+                .data
+                .as_ref()
+                .and_then(|data| serde_json::from_value::<Vec<TextEdit>>(data.to_owned()).ok())
+        })
+        .filter(|edits| !edits.is_empty())
+        .collect::<Vec<Vec<TextEdit>>>();
+    fixes.sort_by_key(|fix| fix.iter().map(|edit| edit.range.start).min());
+    let mut edits: Vec<TextEdit> = Vec::new();
+    for fix in fixes {
+        if !fix.iter().any(|edit| {
+            edits
+                .iter()
+                .any(|accepted| ranges_overlap(&edit.range, &accepted.range))
+        }) {
+            edits.extend(fix);
+        }
+    }
+    return edits;
+}
+
+fn severity_rank(severity: Option<DiagnosticSeverity>) -> u8 {
+    return match severity {
+        Some(DiagnosticSeverity::ERROR) => 0,
+        Some(DiagnosticSeverity::WARNING) => 1,
+        Some(DiagnosticSeverity::INFORMATION) => 2,
+        Some(DiagnosticSeverity::HINT) => 3,
+        _ => 4,
+    };
+}
+
+fn ranges_overlap(a: &Range, b: &Range) -> bool {
+    return a == b || (a.start < b.end && b.start < a.end);
+}
+
+/**
+ * the attributes following the given tag opening. malformed attributes in between are skipped, so
+ * that the ones after them are still found.
+ */
 fn collect_attributes<'a>(mut node: Node<'a>) -> HashMap<&'a str, Node<'a>> {
     let mut attributes = HashMap::new();
-    loop {
-        if let Some(sibling) = node
-            .next_sibling()
-            .filter(|n| n.kind().ends_with("_attribute"))
-        {
-            if let Some(value) = sibling.child(0) {
-                attributes.insert(value.kind(), sibling);
-                node = sibling;
-                continue;
-            }
+    while let Some(sibling) = node.next_sibling() {
+        node = sibling;
+        if sibling.is_error() {
+            continue;
+        }
+        if !sibling.kind().ends_with("_attribute") {
+            break;
+        }
+        if let Some(name) = sibling.child(0) {
+            attributes.insert(name.kind(), sibling);
         }
-        return attributes;
     }
+    return attributes;
 }
 
 fn construct_generate_default_header<'a>(uri: &Url) -> CodeActionOrCommand {
@@ -151,7 +283,7 @@ fn construct_generate_default_header<'a>(uri: &Url) -> CodeActionOrCommand {
                         start: document_start,
                         end: document_start,
                     },
-                    new_text: DEFAULT_HEADER.to_string(),
+                    new_text: default_header(config::get()),
                 }],
             )])),
             ..WorkspaceEdit::default()
@@ -176,6 +308,54 @@ fn construct_fix_spel_syntax<'a>(
     });
 }
 
+fn construct_remove_deprecated_tag(uri: &Url, edits: Vec<TextEdit>) -> CodeActionOrCommand {
+    return CodeActionOrCommand::CodeAction(CodeAction {
+        title: "remove deprecated tag".to_string(),
+        kind: Some(CodeActionImplementation::RemoveDeprecatedTag.to_kind()),
+        edit: Some(WorkspaceEdit {
+            changes: Some(HashMap::from([(uri.clone(), edits)])),
+            ..WorkspaceEdit::default()
+        }),
+        ..CodeAction::default()
+    });
+}
+
+fn construct_remove_unused_taglib(uri: &Url, edits: Vec<TextEdit>) -> CodeActionOrCommand {
+    return CodeActionOrCommand::CodeAction(CodeAction {
+        title: "remove unused taglib import".to_string(),
+        kind: Some(CodeActionImplementation::RemoveUnusedTaglib.to_kind()),
+        edit: Some(WorkspaceEdit {
+            changes: Some(HashMap::from([(uri.clone(), edits)])),
+            ..WorkspaceEdit::default()
+        }),
+        ..CodeAction::default()
+    });
+}
+
+fn construct_add_missing_taglib(uri: &Url, edits: Vec<TextEdit>) -> CodeActionOrCommand {
+    return CodeActionOrCommand::CodeAction(CodeAction {
+        title: "add missing taglib import".to_string(),
+        kind: Some(CodeActionImplementation::AddMissingTaglib.to_kind()),
+        edit: Some(WorkspaceEdit {
+            changes: Some(HashMap::from([(uri.clone(), edits)])),
+            ..WorkspaceEdit::default()
+        }),
+        ..CodeAction::default()
+    });
+}
+
+fn construct_add_content_type(uri: &Url, edits: Vec<TextEdit>) -> CodeActionOrCommand {
+    return CodeActionOrCommand::CodeAction(CodeAction {
+        title: "add contentType to page header".to_string(),
+        kind: Some(CodeActionImplementation::AddContentType.to_kind()),
+        edit: Some(WorkspaceEdit {
+            changes: Some(HashMap::from([(uri.clone(), edits)])),
+            ..WorkspaceEdit::default()
+        }),
+        ..CodeAction::default()
+    });
+}
+
 fn construct_name_to_condition<'a>(
     document: &Document,
     uri: &Url,
@@ -263,84 +443,370 @@ fn construct_condition_to_name<'a>(
         None => return None,
     };
     let offset = value_node.start_position();
-    match document.spel.get(&offset) {
-        Some(SpelAst::Condition(SpelResult::Valid(Condition::Comparisson {
+    let (name, operator_name, value) = match document.spel.get(&offset) {
+        Some(SpelAst::Condition(SpelResult::Valid(condition))) => condition_to_name(condition)?,
+        _ => return None,
+    };
+    return Some(CodeActionOrCommand::CodeAction(CodeAction {
+        title: format!(
+            "transform \"condition\" to \"name\" and \"{}\"",
+            operator_name
+        ),
+        kind: Some(CodeActionImplementation::ConditionToName.to_kind()),
+        edit: Some(WorkspaceEdit {
+            changes: Some(HashMap::from([(
+                uri.clone(),
+                vec![TextEdit {
+                    range: node_range(&condition_node),
+                    new_text: format!("name=\"{}\" {}=\"{}\"", name, operator_name, value),
+                }],
+            )])),
+            ..WorkspaceEdit::default()
+        }),
+        ..CodeAction::default()
+    }));
+}
+
+/**
+ * the name, operator attribute and value equivalent to the given condition. brackets are
+ * removed and negations are resolved by inverting the operator. conditions combining multiple
+ * comparissons cannot be represented.
+ */
+fn condition_to_name(condition: &Condition) -> Option<(String, &'static str, String)> {
+    if let Some((name, value)) = parse_is_null(condition) {
+        return Some((name, "isNull", value));
+    }
+    return match condition {
+        Condition::BracketedCondition { condition, .. } => condition_to_name(condition),
+        Condition::NegatedCondition { condition, .. } => {
+            let (name, operator_name, value) = condition_to_name(condition)?;
+            match operator_name {
+                "isNull" if value == "true" => Some((name, "isNull", "false".to_string())),
+                "isNull" if value == "false" => Some((name, "isNull", "true".to_string())),
+                "eq" => Some((name, "neq", value)),
+                "neq" => Some((name, "eq", value)),
+                "gt" => Some((name, "lte", value)),
+                "gte" => Some((name, "lt", value)),
+                "lt" => Some((name, "gte", value)),
+                "lte" => Some((name, "gt", value)),
+                _ => None,
+            }
+        }
+        Condition::Comparisson {
             left,
             operator,
             right,
             ..
-        }))) => {
-            let operator_name;
-            let new_text;
-            match (&**left, &**right) {
-                (Comparable::Object(inner), _) => {
-                    operator_name = match operator {
+        } => {
+            let (object, other, operator) = match (&**left, &**right) {
+                (Comparable::Object(object), other) => (object, other, operator.clone()),
+                (other, Comparable::Object(object)) => (
+                    object,
+                    other,
+                    match operator {
+                        ComparissonOperator::GreaterThan => ComparissonOperator::LessThan,
+                        ComparissonOperator::GreaterThanOrEqual => {
+                            ComparissonOperator::LessThanOrEqual
+                        }
+                        ComparissonOperator::LessThan => ComparissonOperator::GreaterThan,
+                        ComparissonOperator::LessThanOrEqual => {
+                            ComparissonOperator::GreaterThanOrEqual
+                        }
+                        operator => operator.clone(),
+                    },
+                ),
+                _ => return None,
+            };
+            let name = object.content.to_string();
+            match (other, operator) {
+                (Comparable::Null(_), ComparissonOperator::Equal) => {
+                    Some((name, "isNull", "true".to_string()))
+                }
+                (Comparable::Null(_), ComparissonOperator::Unequal) => {
+                    Some((name, "isNull", "false".to_string()))
+                }
+                (Comparable::Null(_), _) => None,
+                (other, operator) => Some((
+                    name,
+                    match operator {
                         ComparissonOperator::Equal => "eq",
                         ComparissonOperator::Unequal => "neq",
                         ComparissonOperator::GreaterThan => "gt",
                         ComparissonOperator::GreaterThanOrEqual => "gte",
                         ComparissonOperator::LessThan => "lt",
                         ComparissonOperator::LessThanOrEqual => "lte",
-                    };
-                    new_text = format!(
-                        "name=\"{}\" {}=\"{}\"",
-                        inner.content, operator_name, *right
-                    );
-                }
-                (_, Comparable::Object(inner)) => {
-                    operator_name = match operator {
-                        ComparissonOperator::Equal => "eq",
-                        ComparissonOperator::Unequal => "neq",
-                        ComparissonOperator::GreaterThan => "lt",
-                        ComparissonOperator::GreaterThanOrEqual => "lte",
-                        ComparissonOperator::LessThan => "gt",
-                        ComparissonOperator::LessThanOrEqual => "gte",
-                    };
-                    new_text =
-                        format!("name=\"{}\" {}=\"{}\"", inner.content, operator_name, *left);
-                }
-                _ => return None,
-            };
-            return Some(CodeActionOrCommand::CodeAction(CodeAction {
-                title: format!(
-                    "transform \"condition\" to \"name\" and \"{}\"",
-                    operator_name
-                ),
-                kind: Some(CodeActionImplementation::ConditionToName.to_kind()),
-                edit: Some(WorkspaceEdit {
-                    changes: Some(HashMap::from([(
-                        uri.clone(),
-                        vec![TextEdit {
-                            range: node_range(&condition_node),
-                            new_text,
-                        }],
-                    )])),
-                    ..WorkspaceEdit::default()
-                }),
-                ..CodeAction::default()
-            }));
-        }
-        Some(SpelAst::Condition(SpelResult::Valid(condition))) => {
-            return parse_is_null(&condition).map(|(name, value)| {
-                CodeActionOrCommand::CodeAction(CodeAction {
-                    title: "transform \"condition\" to \"name\" and \"isNull\"".to_string(),
-                    kind: Some(CodeActionImplementation::ConditionToName.to_kind()),
-                    edit: Some(WorkspaceEdit {
-                        changes: Some(HashMap::from([(
-                            uri.clone(),
-                            vec![TextEdit {
-                                range: node_range(&condition_node),
-                                new_text: format!("name=\"{}\" isNull=\"{}\"", name, value),
-                            }],
-                        )])),
-                        ..WorkspaceEdit::default()
-                    }),
-                    ..CodeAction::default()
-                })
-            })
+                    },
+                    other.to_string(),
+                )),
+            }
         }
         _ => None,
+    };
+}
+
+fn construct_wrap_in_if(
+    document: &Document,
+    uri: &Url,
+    range: &Range,
+) -> Option<CodeActionOrCommand> {
+    let start = position_to_byte(&document.text, &range.start)?;
+    let end = position_to_byte(&document.text, &range.end)?;
+    if start >= end {
+        return None;
+    }
+    let selected = document.text.get(start..end)?;
+    let end = end - (selected.len() - selected.trim_end().len());
+    let start = start + (selected.len() - selected.trim_start().len());
+    if start >= end {
+        return None;
+    }
+    let ancestor = document
+        .tree
+        .root_node()
+        .descendant_for_byte_range(start, end)?;
+    let nodes: Vec<Node> = match ancestor.start_byte() == start && ancestor.end_byte() == end {
+        true => vec![ancestor],
+        false => {
+            let children: Vec<Node> = ancestor.children(&mut ancestor.walk()).collect();
+            let first = children.iter().position(|n| n.start_byte() == start)?;
+            let last = children.iter().position(|n| n.end_byte() == end)?;
+            children.get(first..=last)?.to_vec()
+        }
+    };
+    if !nodes.iter().all(is_wrappable) {
+        return None;
+    }
+    let first = nodes.first()?;
+    let last = nodes.last()?;
+    let line_start = document.text[..first.start_byte()]
+        .rfind('\n')
+        .map(|index| index + 1)
+        .unwrap_or(0);
+    let indent: String = document.text[line_start..]
+        .chars()
+        .take_while(|c| *c == ' ' || *c == '\t')
+        .collect();
+    let content = document.text[first.start_byte()..last.end_byte()]
+        .lines()
+        .enumerate()
+        .map(|(index, line)| match (index, line.is_empty()) {
+            (0, _) => format!("{}\t{}", indent, line),
+            (_, true) => String::new(),
+            (_, false) => format!("\t{}", line),
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+    return Some(CodeActionOrCommand::CodeAction(CodeAction {
+        title: "wrap in <sp:if>".to_string(),
+        kind: Some(CodeActionImplementation::WrapInIf.to_kind()),
+        edit: Some(WorkspaceEdit {
+            changes: Some(HashMap::from([(
+                uri.clone(),
+                vec![TextEdit {
+                    range: Range {
+                        start: point_to_position(&first.start_position()),
+                        end: point_to_position(&last.end_position()),
+                    },
+                    new_text: format!("<sp:if condition=\"\">\n{}\n{}</sp:if>", content, indent),
+                }],
+            )])),
+            ..WorkspaceEdit::default()
+        }),
+        ..CodeAction::default()
+    }));
+}
+
+/**
+ * moves the innermost interpolation around the cursor into an sp:set in front of the tag
+ * containing it. only offered in object and expression attributes, as conditions and strings
+ * cannot simply refer to an object.
+ */
+fn construct_extract_variable(
+    document: &Document,
+    uri: &Url,
+    position: Position,
+) -> Option<CodeActionOrCommand> {
+    let node = parser::find_current_node(&document.tree, position)?;
+    match document.spel.get(&node.start_position()) {
+        Some(SpelAst::Object(_)) | Some(SpelAst::Expression(_)) => {}
+        _ => return None,
     }
+    let range = selection::spel_ranges(document, &node, position)
+        .into_iter()
+        .filter(|range| range.start.line == range.end.line)
+        .rfind(|range| {
+            position_to_byte(&document.text, &range.start)
+                .and_then(|start| document.text.get(start..))
+                .is_some_and(|text| text.starts_with("${"))
+        })?;
+    let start = position_to_byte(&document.text, &range.start)?;
+    let end = position_to_byte(&document.text, &range.end)?;
+    let extracted = document.text.get(start + 2..end - 1)?;
+    let mut tag = node.parent();
+    while let Some(current) = tag.filter(|tag| !tag.kind().ends_with("_tag")) {
+        tag = current.parent();
+    }
+    let tag = tag?;
+    let line_start = document.text[..tag.start_byte()]
+        .rfind('\n')
+        .map(|index| index + 1)
+        .unwrap_or(0);
+    let indent: String = document.text[line_start..tag.start_byte()]
+        .chars()
+        .take_while(|c| *c == ' ' || *c == '\t')
+        .collect();
+    let name = unused_variable_name(&document.text, "_extracted");
+    let tag_start = point_to_position(&tag.start_position());
+    return Some(CodeActionOrCommand::CodeAction(CodeAction {
+        title: "extract into variable".to_string(),
+        kind: Some(CodeActionImplementation::ExtractVariable.to_kind()),
+        edit: Some(WorkspaceEdit {
+            changes: Some(HashMap::from([(
+                uri.clone(),
+                vec![
+                    TextEdit {
+                        range: Range {
+                            start: tag_start,
+                            end: tag_start,
+                        },
+                        new_text: format!(
+                            "<sp:set name=\"{}\" object=\"{}\"/>\n{}",
+                            name, extracted, indent
+                        ),
+                    },
+                    TextEdit {
+                        range,
+                        new_text: format!("${{{}}}", name),
+                    },
+                ],
+            )])),
+            ..WorkspaceEdit::default()
+        }),
+        ..CodeAction::default()
+    }));
+}
+
+/**
+ * the given name, suffixed with the lowest number necessary for it to not occur anywhere in the
+ * text. such a name cannot be declared already.
+ */
+fn unused_variable_name(text: &str, name: &str) -> String {
+    if !text.contains(name) {
+        return name.to_string();
+    }
+    let mut suffix = 2;
+    while text.contains(&format!("{}{}", name, suffix)) {
+        suffix += 1;
+    }
+    return format!("{}{}", name, suffix);
+}
+
+/**
+ * rewrites the spel attribute value under the cursor in its canonical form.
+ */
+fn construct_format_spel(
+    document: &Document,
+    uri: &Url,
+    position: Position,
+) -> Option<CodeActionOrCommand> {
+    let mut node = parser::find_current_node(&document.tree, position)?;
+    while !node.kind().ends_with("_attribute") {
+        node = node.parent()?;
+    }
+    let string = node.child(2)?;
+    let (start, content) = parser::string_content_of(string, &document.text)?;
+    let formatted = format::format(document.spel.get(&start)?)?;
+    if content == formatted {
+        return None;
+    }
+    // the value may consist of multiple string_content and xml_entity nodes.
+    let end = string.child(string.child_count() - 1)?.start_position();
+    return Some(CodeActionOrCommand::CodeAction(CodeAction {
+        title: "format spel".to_string(),
+        kind: Some(CodeActionImplementation::FormatSpel.to_kind()),
+        edit: Some(WorkspaceEdit {
+            changes: Some(HashMap::from([(
+                uri.clone(),
+                vec![TextEdit {
+                    range: Range {
+                        start: point_to_position(&start),
+                        end: point_to_position(&end),
+                    },
+                    new_text: formatted,
+                }],
+            )])),
+            ..WorkspaceEdit::default()
+        }),
+        ..CodeAction::default()
+    }));
+}
+
+/**
+ * reorders the attributes of the tag around the cursor alphabetically, which is also the order
+ * in which they are declared in the grammar. the whitespace between the attributes is kept.
+ */
+fn construct_sort_attributes(
+    document: &Document,
+    uri: &Url,
+    position: Position,
+) -> Option<CodeActionOrCommand> {
+    let mut node = parser::find_current_node(&document.tree, position)?;
+    let mut in_tag_open = false;
+    while !node.kind().ends_with("_tag") {
+        let kind = node.kind();
+        in_tag_open |= kind.ends_with("_attribute") || kind.ends_with("_tag_open");
+        node = node.parent()?;
+    }
+    if !in_tag_open {
+        return None;
+    }
+    let attributes: Vec<Node> = node
+        .children(&mut node.walk())
+        .filter(|child| child.kind().ends_with("_attribute"))
+        .collect();
+    let mut sorted = attributes.clone();
+    sorted.sort_by_key(|attribute| {
+        parser::attribute_name_of(*attribute, &document.text).map(|name| name.to_lowercase())
+    });
+    if sorted == attributes {
+        return None;
+    }
+    let first = attributes.first()?;
+    let last = attributes.last()?;
+    let mut new_text = String::new();
+    for (index, attribute) in sorted.iter().enumerate() {
+        if index > 0 {
+            new_text.push_str(
+                &document.text[attributes[index - 1].end_byte()..attributes[index].start_byte()],
+            );
+        }
+        new_text.push_str(&document.text[attribute.start_byte()..attribute.end_byte()]);
+    }
+    return Some(CodeActionOrCommand::CodeAction(CodeAction {
+        title: "sort attributes".to_string(),
+        kind: Some(CodeActionImplementation::SortAttributes.to_kind()),
+        edit: Some(WorkspaceEdit {
+            changes: Some(HashMap::from([(
+                uri.clone(),
+                vec![TextEdit {
+                    range: Range {
+                        start: point_to_position(&first.start_position()),
+                        end: point_to_position(&last.end_position()),
+                    },
+                    new_text,
+                }],
+            )])),
+            ..WorkspaceEdit::default()
+        }),
+        ..CodeAction::default()
+    }));
+}
+
+fn is_wrappable(node: &Node<'_>) -> bool {
+    return match node.kind() {
+        "text" | "comment" | "xml_comment" | "xml_entity" => true,
+        kind => kind.ends_with("_tag"),
+    };
 }
 
 fn parse_is_null(root: &Condition) -> Option<(String, String)> {
@@ -391,9 +857,457 @@ fn node_range(node: &Node<'_>) -> Range {
     };
 }
 
+/**
+ * converts a position sent by the client, whose character is counted in utf-16 code units, into a
+ * byte offset. positions inside of a character or behind the end of their line yield `None`.
+ */
+fn position_to_byte(text: &str, position: &Position) -> Option<usize> {
+    let mut offset = 0;
+    for (index, line) in text.split_inclusive('\n').enumerate() {
+        if index == position.line as usize {
+            let mut units = 0;
+            for (byte, character) in line.char_indices() {
+                if units == position.character as usize {
+                    return Some(offset + byte);
+                }
+                units += character.len_utf16();
+            }
+            return match units == position.character as usize {
+                true => Some(offset + line.len()),
+                false => None,
+            };
+        }
+        offset += line.len();
+    }
+    return match position.character {
+        0 => Some(offset),
+        _ => None,
+    };
+}
+
 fn point_to_position(point: &Point) -> Position {
     return Position {
         line: point.row as u32,
         character: point.column as u32,
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use lsp_types::{
+        CodeActionOrCommand, Diagnostic, DiagnosticSeverity, NumberOrString, Position, Range,
+        TextEdit, Url,
+    };
+    use tree_sitter::Point;
+
+    use crate::{
+        capabilities::CodeActionImplementation, config::Config, document_store::test_document,
+        spel::parser::Parser,
+    };
+
+    use super::{
+        collect_attributes, collect_fix_all_edits, collect_spel_syntax_edits, condition_to_name,
+        construct_extract_variable, construct_format_spel, construct_sort_attributes,
+        construct_wrap_in_if, default_header, position_to_byte,
+    };
+
+    #[test]
+    fn test_wrap_in_if() {
+        let uri = Url::parse("file:///some/test/file.spml").unwrap();
+        let document = test_document("<sp:print name=\"_a\"/>\n<sp:print name=\"_b\"/>\n");
+        let range = Range {
+            start: Position {
+                line: 3,
+                character: 0,
+            },
+            end: Position {
+                line: 4,
+                character: 21,
+            },
+        };
+        let action = match construct_wrap_in_if(&document, &uri, &range) {
+            Some(CodeActionOrCommand::CodeAction(action)) => action,
+            action => panic!("expected a code action, got {:?}", action),
+        };
+        assert_eq!(
+            action.edit.unwrap().changes.unwrap().remove(&uri),
+            Some(vec![TextEdit {
+                range: Range {
+                    start: Position {
+                        line: 3,
+                        character: 0,
+                    },
+                    end: Position {
+                        line: 4,
+                        character: 21,
+                    },
+                },
+                new_text: concat!(
+                    "<sp:if condition=\"\">\n",
+                    "\t<sp:print name=\"_a\"/>\n",
+                    "\t<sp:print name=\"_b\"/>\n",
+                    "</sp:if>"
+                )
+                .to_string(),
+            }])
+        );
+    }
+
+    #[test]
+    fn test_wrap_in_if_partial_tag() {
+        let uri = Url::parse("file:///some/test/file.spml").unwrap();
+        let document = test_document("<sp:print name=\"_a\"/>\n");
+        let range = Range {
+            start: Position {
+                line: 3,
+                character: 4,
+            },
+            end: Position {
+                line: 3,
+                character: 21,
+            },
+        };
+        assert_eq!(construct_wrap_in_if(&document, &uri, &range), None);
+    }
+
+    fn edit(line: u32, start: u32, end: u32, new_text: &str) -> TextEdit {
+        return TextEdit {
+            range: Range {
+                start: Position {
+                    line,
+                    character: start,
+                },
+                end: Position {
+                    line,
+                    character: end,
+                },
+            },
+            new_text: new_text.to_string(),
+        };
+    }
+
+    fn fixable(
+        code: NumberOrString,
+        severity: DiagnosticSeverity,
+        edits: Vec<TextEdit>,
+    ) -> Diagnostic {
+        return Diagnostic {
+            range: edits[0].range,
+            severity: Some(severity),
+            code: Some(code),
+            data: serde_json::to_value(edits).ok(),
+            ..Default::default()
+        };
+    }
+
+    #[test]
+    fn test_extract_variable() {
+        let uri = Url::parse("file:///some/test/file.spml").unwrap();
+        let document = test_document(concat!(
+            "<div>\n",
+            "\t<sp:set name=\"_c\" object=\"_a.get(${_b.length()})\"/>\n",
+            "\t<sp:print name=\"_extracted\"/>\n",
+            "</div>\n",
+        ));
+        let position = Position {
+            line: 4,
+            character: 37,
+        };
+        let action = match construct_extract_variable(&document, &uri, position) {
+            Some(CodeActionOrCommand::CodeAction(action)) => action,
+            action => panic!("expected a code action, got {:?}", action),
+        };
+        assert_eq!(
+            action.edit.unwrap().changes.unwrap().remove(&uri),
+            Some(vec![
+                TextEdit {
+                    range: Range {
+                        start: Position {
+                            line: 4,
+                            character: 1,
+                        },
+                        end: Position {
+                            line: 4,
+                            character: 1,
+                        },
+                    },
+                    new_text: "<sp:set name=\"_extracted2\" object=\"_b.length()\"/>\n\t"
+                        .to_string(),
+                },
+                TextEdit {
+                    range: Range {
+                        start: Position {
+                            line: 4,
+                            character: 34,
+                        },
+                        end: Position {
+                            line: 4,
+                            character: 48,
+                        },
+                    },
+                    new_text: "${_extracted2}".to_string(),
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn test_extract_variable_not_in_condition() {
+        let uri = Url::parse("file:///some/test/file.spml").unwrap();
+        let document =
+            test_document("<sp:if condition=\"${_a.size} > ${_b.length()}\">\n</sp:if>\n");
+        let position = Position {
+            line: 3,
+            character: 33,
+        };
+        assert_eq!(construct_extract_variable(&document, &uri, position), None);
+    }
+
+    #[test]
+    fn test_sort_attributes() {
+        let uri = Url::parse("file:///some/test/file.spml").unwrap();
+        let document = test_document("<sp:set value=\"${_b}\" name=\"_a\"  default=\"x\"/>\n");
+        let position = Position {
+            line: 3,
+            character: 10,
+        };
+        let action = match construct_sort_attributes(&document, &uri, position) {
+            Some(CodeActionOrCommand::CodeAction(action)) => action,
+            action => panic!("expected a code action, got {:?}", action),
+        };
+        assert_eq!(
+            action.edit.unwrap().changes.unwrap().remove(&uri),
+            Some(vec![edit(
+                3,
+                8,
+                44,
+                "default=\"x\" name=\"_a\"  value=\"${_b}\""
+            )])
+        );
+    }
+
+    #[test]
+    fn test_format_spel() {
+        let uri = Url::parse("file:///some/test/file.spml").unwrap();
+        let document = test_document("<sp:if condition=\"${_a}=='b'&&!(${_c}>1)\">\n</sp:if>\n");
+        let action = match construct_format_spel(&document, &uri, Position::new(3, 20)) {
+            Some(CodeActionOrCommand::CodeAction(action)) => action,
+            action => panic!("expected a code action, got {:?}", action),
+        };
+        assert_eq!(
+            action.edit.unwrap().changes.unwrap().remove(&uri),
+            Some(vec![edit(3, 18, 40, "${_a} == 'b' && !(${_c} > 1)")])
+        );
+    }
+
+    #[test]
+    fn test_format_spel_with_xml_entity() {
+        let uri = Url::parse("file:///some/test/file.spml").unwrap();
+        let document = test_document("<sp:if condition=\"${_a}=='&amp;'&&${_c}\">\n</sp:if>\n");
+        let action = match construct_format_spel(&document, &uri, Position::new(3, 20)) {
+            Some(CodeActionOrCommand::CodeAction(action)) => action,
+            action => panic!("expected a code action, got {:?}", action),
+        };
+        assert_eq!(
+            action.edit.unwrap().changes.unwrap().remove(&uri),
+            Some(vec![edit(3, 18, 39, "${_a} == '&amp;' && ${_c}")])
+        );
+    }
+
+    fn convert_condition(condition: &str) -> Option<(String, &'static str, String)> {
+        let condition = Parser::new(condition).parse_condition_ast().unwrap().root;
+        return condition_to_name(&condition);
+    }
+
+    fn converted(
+        name: &str,
+        operator: &'static str,
+        value: &str,
+    ) -> Option<(String, &'static str, String)> {
+        return Some((name.to_string(), operator, value.to_string()));
+    }
+
+    #[test]
+    fn test_condition_to_name_comparisson_operators() {
+        assert_eq!(
+            convert_condition("${_x} == 'y'"),
+            converted("_x", "eq", "y")
+        );
+        assert_eq!(
+            convert_condition("${_x} != 'y'"),
+            converted("_x", "neq", "y")
+        );
+        assert_eq!(convert_condition("${_x} > 1"), converted("_x", "gt", "1"));
+        assert_eq!(convert_condition("${_x} >= 1"), converted("_x", "gte", "1"));
+        assert_eq!(convert_condition("${_x} < 1"), converted("_x", "lt", "1"));
+        assert_eq!(convert_condition("${_x} <= 1"), converted("_x", "lte", "1"));
+        assert_eq!(convert_condition("1 < ${_x}"), converted("_x", "gt", "1"));
+    }
+
+    #[test]
+    fn test_condition_to_name_null_checks() {
+        assert_eq!(
+            convert_condition("${_x} == null"),
+            converted("_x", "isNull", "true")
+        );
+        assert_eq!(
+            convert_condition("${_x} != null"),
+            converted("_x", "isNull", "false")
+        );
+        assert_eq!(
+            convert_condition("isNull(${_x})"),
+            converted("_x", "isNull", "true")
+        );
+        assert_eq!(
+            convert_condition("!isNull(${_x})"),
+            converted("_x", "isNull", "false")
+        );
+    }
+
+    #[test]
+    fn test_condition_to_name_bracketed_and_negated() {
+        assert_eq!(
+            convert_condition("(${_x} == 'y')"),
+            converted("_x", "eq", "y")
+        );
+        assert_eq!(
+            convert_condition("!(${_x} == 'y')"),
+            converted("_x", "neq", "y")
+        );
+        assert_eq!(
+            convert_condition("!(${_x} > 1)"),
+            converted("_x", "lte", "1")
+        );
+        assert_eq!(
+            convert_condition("!(${_x} == null)"),
+            converted("_x", "isNull", "false")
+        );
+    }
+
+    #[test]
+    fn test_condition_to_name_compound_condition() {
+        assert_eq!(convert_condition("${_x} == 'y' && ${_z} > 1"), None);
+    }
+
+    #[test]
+    fn test_position_to_byte_counts_utf16_units() {
+        let text = "<p>ä😀x</p>\n<br>";
+        let position = |line, character| Position { line, character };
+        assert_eq!(position_to_byte(text, &position(0, 4)), Some(5));
+        assert_eq!(position_to_byte(text, &position(0, 6)), Some(9));
+        assert_eq!(position_to_byte(text, &position(0, 5)), None);
+        assert_eq!(position_to_byte(text, &position(1, 4)), Some(text.len()));
+        assert_eq!(position_to_byte(text, &position(1, 5)), None);
+    }
+
+    #[test]
+    fn test_fix_all_combines_non_overlapping_edits() {
+        let edits = collect_fix_all_edits(&[
+            fixable(
+                CodeActionImplementation::ADD_MISSING_TAGLIB_CODE,
+                DiagnosticSeverity::WARNING,
+                vec![edit(5, 0, 20, "")],
+            ),
+            fixable(
+                CodeActionImplementation::FIX_SPEL_SYNTAX_CODE,
+                DiagnosticSeverity::ERROR,
+                vec![edit(3, 26, 26, "}")],
+            ),
+            fixable(
+                CodeActionImplementation::REMOVE_UNUSED_TAGLIB_CODE,
+                DiagnosticSeverity::HINT,
+                vec![edit(5, 10, 30, "")],
+            ),
+        ]);
+        assert_eq!(edits, vec![edit(3, 26, 26, "}"), edit(5, 0, 20, "")]);
+    }
+
+    #[test]
+    fn test_fix_all_skips_deprecated_tags() {
+        let edits = collect_fix_all_edits(&[
+            fixable(
+                CodeActionImplementation::REMOVE_DEPRECATED_TAG_CODE,
+                DiagnosticSeverity::INFORMATION,
+                vec![edit(5, 0, 20, "")],
+            ),
+            fixable(
+                CodeActionImplementation::FIX_SPEL_SYNTAX_CODE,
+                DiagnosticSeverity::ERROR,
+                vec![edit(3, 26, 26, "}")],
+            ),
+        ]);
+        assert_eq!(edits, vec![edit(3, 26, 26, "}")]);
+    }
+
+    #[test]
+    fn test_spel_syntax_edits_prefer_earlier_fixes() {
+        let edits = collect_spel_syntax_edits(&[
+            fixable(
+                CodeActionImplementation::FIX_SPEL_SYNTAX_CODE,
+                DiagnosticSeverity::ERROR,
+                vec![edit(5, 10, 30, "")],
+            ),
+            fixable(
+                CodeActionImplementation::REMOVE_DEPRECATED_TAG_CODE,
+                DiagnosticSeverity::INFORMATION,
+                vec![edit(3, 0, 20, "")],
+            ),
+            fixable(
+                CodeActionImplementation::FIX_SPEL_SYNTAX_CODE,
+                DiagnosticSeverity::ERROR,
+                vec![edit(5, 0, 20, ")")],
+            ),
+            fixable(
+                CodeActionImplementation::FIX_SPEL_SYNTAX_CODE,
+                DiagnosticSeverity::ERROR,
+                vec![edit(3, 26, 26, "}")],
+            ),
+        ]);
+        assert_eq!(edits, vec![edit(3, 26, 26, "}"), edit(5, 0, 20, ")")]);
+    }
+
+    #[test]
+    fn test_collect_attributes_after_malformed_attribute() {
+        let document = test_document("<sp:if name=\"_a\" \"broken\" isNull=\"true\">\n</sp:if>\n");
+        let tag_open = document
+            .tree
+            .root_node()
+            .descendant_for_point_range(Point::new(3, 1), Point::new(3, 1))
+            .unwrap();
+        assert_eq!(tag_open.kind(), "if_tag_open");
+        let mut names: Vec<&str> = collect_attributes(tag_open).into_keys().collect();
+        names.sort();
+        assert_eq!(names, vec!["isNull", "name"]);
+    }
+
+    #[test]
+    fn test_default_header() {
+        assert_eq!(
+            default_header(&Config::default()),
+            concat!(
+                "<%@ page language=\"java\" pageEncoding=\"UTF-8\" contentType=\"text/html; charset=UTF-8\"\n",
+                "%><%@ taglib uri=\"http://www.sitepark.com/taglibs/core\" prefix=\"sp\"\n",
+                "%><%@ taglib tagdir=\"/WEB-INF/tags/spt\" prefix=\"spt\"\n",
+                "%>\n"
+            )
+        );
+    }
+
+    #[test]
+    fn test_configured_default_header() {
+        let config = Config {
+            content_type: String::from("application/json; charset=ISO-8859-1"),
+            page_encoding: String::from("ISO-8859-1"),
+            default_taglibs: vec![String::from("sp")],
+            ..Config::default()
+        };
+        assert_eq!(
+            default_header(&config),
+            concat!(
+                "<%@ page language=\"java\" pageEncoding=\"ISO-8859-1\" contentType=\"application/json; charset=ISO-8859-1\"\n",
+                "%><%@ taglib uri=\"http://www.sitepark.com/taglibs/core\" prefix=\"sp\"\n",
+                "%>\n"
+            )
+        );
+    }
+}
@@ -3,8 +3,9 @@ use std::{cmp::Ordering, collections::HashMap, fs, iter::Iterator, str::FromStr}
 use anyhow::Result;
 use lsp_server::ErrorCode;
 use lsp_types::{
-    CompletionItem, CompletionItemKind, CompletionParams, CompletionTextEdit, Documentation,
-    MarkupContent, MarkupKind, Position, Range, TextDocumentPositionParams, TextEdit, Url,
+    CompletionItem, CompletionItemKind, CompletionItemLabelDetails, CompletionItemTag,
+    CompletionParams, CompletionTextEdit, Documentation, InsertTextFormat, MarkupContent,
+    MarkupKind, Position, Range, TextDocumentPositionParams, TextEdit, Url,
 };
 use tree_sitter::{Node, Point};
 
@@ -120,7 +121,16 @@ impl CompletionCollector<'_> {
 
     fn complete_tags<'a>(&mut self, tags: impl Iterator<Item = &'a TagDefinition>) {
         let range = self.determine_tag_range();
-        tags.map(|tag| Self::tag_to_completion(tag, range))
+        let typed = self
+            .document
+            .text
+            .lines()
+            .nth(self.cursor.row)
+            .and_then(|line| line.get(range.start.character as usize..self.cursor.column))
+            .unwrap_or("")
+            .to_string();
+        tags.filter(|tag| typed.is_empty() || format!("<{}", tag.name).starts_with(&typed))
+            .map(|tag| Self::tag_to_completion(tag, range))
             .for_each(|completion| self.completions.push(completion));
     }
 
@@ -193,10 +203,20 @@ impl CompletionCollector<'_> {
     }
 
     fn tag_to_completion(tag: &TagDefinition, range: Range) -> CompletionItem {
-        let new_text = format!("<{}", tag.name);
+        let label = format!("<{}", tag.name);
+        let mut new_text = label.clone();
+        let mut placeholder = 0;
+        for rule in tag.attribute_rules {
+            if let grammar::AttributeRule::Required(name) = rule {
+                placeholder += 1;
+                new_text += &format!(" {}=\"${}\"", name, placeholder);
+            }
+        }
         return CompletionItem {
-            label: new_text.clone(),
+            label,
             kind: Some(CompletionItemKind::KEYWORD),
+            tags: tag.deprecated.then(|| vec![CompletionItemTag::DEPRECATED]),
+            insert_text_format: Some(InsertTextFormat::SNIPPET),
             detail: tag.detail.map(|detail| detail.to_string()),
             documentation: tag.documentation.map(|detail| {
                 Documentation::MarkupContent(MarkupContent {
@@ -421,6 +441,18 @@ impl CompletionCollector<'_> {
                     }
                 }
             }
+            Some(TagAttributeType::Object) => {
+                let mut names = Vec::new();
+                self.collect_declared_variables(self.document.tree.root_node(), &mut names);
+                for name in names {
+                    self.completions.push(CompletionItem {
+                        label: name.clone(),
+                        kind: Some(CompletionItemKind::VARIABLE),
+                        insert_text: Some(name),
+                        ..Default::default()
+                    });
+                }
+            }
             Some(TagAttributeType::Module) => {
                 modules::all_modules().iter().for_each(|(name, _)| {
                     self.completions.push(CompletionItem {
@@ -458,6 +490,40 @@ impl CompletionCollector<'_> {
         return Ok(());
     }
 
+    /**
+     * names declared by identifier attributes of tags preceding the cursor. loop items are only
+     * proposed inside of their loop.
+     */
+    fn collect_declared_variables(&self, node: Node, names: &mut Vec<String>) {
+        for child in node.children(&mut node.walk()) {
+            if child.start_position() >= self.cursor {
+                return;
+            }
+            if let Ok(tag) = TagDefinition::from_str(child.kind()) {
+                for attribute in child.children(&mut child.walk()) {
+                    let (name, value) =
+                        match parser::attribute_name_and_value_of(attribute, &self.document.text) {
+                            Some((name, value)) if !value.is_empty() && !value.contains("${") => {
+                                (name, value)
+                            }
+                            _ => continue,
+                        };
+                    if name == "item" && child.end_position() <= self.cursor {
+                        continue;
+                    }
+                    if let Some(TagAttributeType::Identifier) =
+                        tag.attributes.get_by_name(name).map(|a| &a.r#type)
+                    {
+                        if !names.iter().any(|known| known == value) {
+                            names.push(value.to_string());
+                        }
+                    }
+                }
+            }
+            self.collect_declared_variables(child, names);
+        }
+    }
+
     fn complete_attributes_of(&mut self, tag: TagDefinition, attributes: HashMap<String, String>) {
         if let TagAttributes::These(possible) = tag.attributes {
             possible
@@ -465,7 +531,26 @@ impl CompletionCollector<'_> {
                 .filter(|attribute| !attributes.contains_key(attribute.name))
                 .map(|attribute| CompletionItem {
                     label: attribute.name.to_string(),
+                    label_details: tag
+                        .attribute_rules
+                        .iter()
+                        .any(|rule| {
+                            matches!(rule, grammar::AttributeRule::Required(name)
+                                if *name == attribute.name)
+                        })
+                        .then(|| CompletionItemLabelDetails {
+                            detail: Some(" (required)".to_string()),
+                            description: None,
+                        }),
                     kind: Some(CompletionItemKind::PROPERTY),
+                    tags: tag
+                        .attribute_rules
+                        .iter()
+                        .any(|rule| {
+                            matches!(rule, grammar::AttributeRule::Deprecated(name)
+                                if *name == attribute.name)
+                        })
+                        .then(|| vec![CompletionItemTag::DEPRECATED]),
                     detail: attribute.detail.map(|detail| detail.to_string()),
                     documentation: attribute.documentation.map(|detail| {
                         Documentation::MarkupContent(MarkupContent {
@@ -491,6 +576,7 @@ impl CompletionCollector<'_> {
 }
 
 pub(crate) fn complete(params: CompletionParams) -> Result<Vec<CompletionItem>, LsError> {
+    let trigger = params.context.and_then(|context| context.trigger_character);
     let text_params = params.text_document_position;
     let uri = &text_params.text_document.uri;
     let document = match document_store::get(uri) {
@@ -513,19 +599,57 @@ pub(crate) fn complete(params: CompletionParams) -> Result<Vec<CompletionItem>,
             message: format!("failed to validate document: {}", err),
             code: ErrorCode::RequestFailed,
         })?;
-    return Ok(completion_collector.completions);
+    return Ok(completions_for_trigger(
+        completion_collector.completions,
+        trigger.as_deref(),
+    ));
+}
+
+/**
+ * a space only triggers attribute completions, while `<` and `:` only trigger tag completions.
+ * completions that were requested explicitly are not filtered.
+ */
+fn completions_for_trigger(
+    completions: Vec<CompletionItem>,
+    trigger: Option<&str>,
+) -> Vec<CompletionItem> {
+    let kind = match trigger {
+        Some(" ") => CompletionItemKind::PROPERTY,
+        Some("<" | ":") => CompletionItemKind::KEYWORD,
+        _ => return completions,
+    };
+    return completions
+        .into_iter()
+        .filter(|completion| completion.kind == Some(kind))
+        .collect();
 }
 
 #[cfg(test)]
 mod tests {
     use lsp_types::{
-        CompletionParams, PartialResultParams, Position, TextDocumentIdentifier,
-        TextDocumentPositionParams, Url, WorkDoneProgressParams,
+        CompletionItem, CompletionItemKind, CompletionItemTag, CompletionParams,
+        CompletionTextEdit, PartialResultParams, Position, Range, TextDocumentIdentifier,
+        TextDocumentPositionParams, TextEdit, Url, WorkDoneProgressParams,
     };
 
-    use crate::document_store::Document;
+    use crate::document_store::{test_document, Document};
 
-    use super::CompletionCollector;
+    use super::{completions_for_trigger, CompletionCollector};
+
+    fn complete_at(body: &str, line: u32, character: u32) -> Vec<CompletionItem> {
+        let params = TextDocumentPositionParams {
+            text_document: TextDocumentIdentifier {
+                uri: Url::parse("file:///some/test/file.spml").unwrap(),
+            },
+            position: Position { line, character },
+        };
+        let document = test_document(body);
+        let mut completion_collector = CompletionCollector::new(&params, &document);
+        completion_collector
+            .search_completions_in_document(document.tree.root_node())
+            .unwrap();
+        return completion_collector.completions;
+    }
 
     #[test]
     fn test_completion_for_attributes_in_nested_tag() {
@@ -578,4 +702,170 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn test_completion_for_closing_tag() {
+        let completions = complete_at(
+            concat!(
+                "<sp:iterator collection=\"${_list}\" item=\"_item\">\n",
+                "\t<sp:print name=\"_item\"/>\n",
+                "</\n",
+            ),
+            5,
+            2,
+        );
+        assert!(completions
+            .iter()
+            .any(|completion| completion.label == "</sp:iterator>"
+                && completion.insert_text.as_deref() == Some("sp:iterator>")));
+    }
+
+    #[test]
+    fn test_completion_for_value_one_of_attribute() {
+        let completions = complete_at("<sp:include mode=\"\" template=\"x\"/>\n", 3, 18);
+        let labels: Vec<&str> = completions
+            .iter()
+            .map(|completion| completion.label.as_str())
+            .collect();
+        assert_eq!(labels, vec!["in", "out"]);
+    }
+
+    #[test]
+    fn test_completion_for_case_insensitive_value_one_of_attribute() {
+        let completions = complete_at("<sp:form method=\"\"></sp:form>\n", 3, 17);
+        let labels: Vec<&str> = completions
+            .iter()
+            .map(|completion| completion.label.as_str())
+            .collect();
+        assert_eq!(labels, vec!["GET", "POST"]);
+    }
+
+    #[test]
+    fn test_completion_for_text_type() {
+        let completions = complete_at("<sp:text name=\"a\" type=\"\"/>\n", 3, 24);
+        let labels: Vec<&str> = completions
+            .iter()
+            .map(|completion| completion.label.as_str())
+            .collect();
+        assert_eq!(labels, vec!["date", "email", "number", "text", "url"]);
+    }
+
+    #[test]
+    fn test_completion_for_declared_variables() {
+        let completions = complete_at(
+            concat!(
+                "<sp:set name=\"_first\" value=\"1\"/>\n",
+                "<sp:set name=\"_second\" value=\"2\"/>\n",
+                "<sp:print name=\"\"/>\n",
+                "<sp:set name=\"_later\" value=\"3\"/>\n",
+            ),
+            5,
+            16,
+        );
+        let labels: Vec<&str> = completions
+            .iter()
+            .map(|completion| completion.label.as_str())
+            .collect();
+        assert_eq!(labels, vec!["_first", "_second"]);
+    }
+
+    #[test]
+    fn test_completion_omits_present_attributes() {
+        let completions = complete_at("<sp:set name=\"_x\" />\n", 3, 18);
+        let labels: Vec<&str> = completions
+            .iter()
+            .map(|completion| completion.label.as_str())
+            .collect();
+        assert!(!labels.contains(&"name"));
+        assert!(labels.contains(&"value"));
+    }
+
+    #[test]
+    fn test_completion_marks_required_attributes() {
+        let completions = complete_at("<sp:set />\n", 3, 8);
+        let name = completions
+            .iter()
+            .find(|completion| completion.label == "name")
+            .unwrap();
+        assert_eq!(
+            name.label_details
+                .as_ref()
+                .and_then(|details| details.detail.as_deref()),
+            Some(" (required)")
+        );
+        let value = completions
+            .iter()
+            .find(|completion| completion.label == "value")
+            .unwrap();
+        assert_eq!(value.label_details, None);
+    }
+
+    #[test]
+    fn test_completion_marks_deprecated_attributes() {
+        let completions = complete_at("<sp:form ></sp:form>\n", 3, 9);
+        let command = completions
+            .iter()
+            .find(|completion| completion.label == "command")
+            .unwrap();
+        assert_eq!(command.tags, Some(vec![CompletionItemTag::DEPRECATED]));
+    }
+
+    #[test]
+    fn test_completion_for_tag_prefix() {
+        let completions = complete_at("<sp:in\n", 3, 6);
+        assert!(completions
+            .iter()
+            .any(|completion| completion.label == "<sp:include"));
+        assert!(completions
+            .iter()
+            .all(|completion| completion.label.starts_with("<sp:in")));
+    }
+
+    #[test]
+    fn test_completion_for_tag_inserts_required_attributes() {
+        let completions = complete_at("<sp:se\n", 3, 6);
+        let set = completions
+            .iter()
+            .find(|completion| completion.label == "<sp:set")
+            .unwrap();
+        assert_eq!(
+            set.text_edit,
+            Some(CompletionTextEdit::Edit(TextEdit {
+                range: Range {
+                    start: Position {
+                        line: 3,
+                        character: 0,
+                    },
+                    end: Position {
+                        line: 3,
+                        character: 6,
+                    },
+                },
+                new_text: "<sp:set name=\"$1\"".to_string(),
+            }))
+        );
+    }
+
+    #[test]
+    fn test_completions_for_trigger_character() {
+        let completion = |label: &str, kind| CompletionItem {
+            label: label.to_string(),
+            kind: Some(kind),
+            ..Default::default()
+        };
+        let completions = vec![
+            completion("<sp:print", CompletionItemKind::KEYWORD),
+            completion("name", CompletionItemKind::PROPERTY),
+            completion("in", CompletionItemKind::ENUM_MEMBER),
+        ];
+        let labels = |trigger| {
+            return completions_for_trigger(completions.clone(), trigger)
+                .into_iter()
+                .map(|completion| completion.label)
+                .collect::<Vec<String>>();
+        };
+        assert_eq!(labels(Some(" ")), vec!["name"]);
+        assert_eq!(labels(Some(":")), vec!["<sp:print"]);
+        assert_eq!(labels(None), vec!["<sp:print", "name", "in"]);
+    }
 }
@@ -1,17 +1,23 @@
-use std::{collections::HashMap, path::Path, str::FromStr};
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
 
 use anyhow::Result;
 use lsp_server::ErrorCode;
 use lsp_types::{
-    Diagnostic, DiagnosticSeverity, DiagnosticTag, DocumentDiagnosticParams, NumberOrString,
-    Position, Range, TextEdit, Url,
+    Diagnostic, DiagnosticSeverity, DiagnosticTag, DocumentDiagnosticParams, Position,
+    PublishDiagnosticsParams, Range, TextEdit, Url,
 };
 use tree_sitter::{Node, Point};
 
 use crate::{
     capabilities::CodeActionImplementation,
+    config::{self, DiagnosticType},
     document_store,
-    grammar::{self, TagChildren, TagDefinition},
+    grammar::{self, TagAttributeType, TagChildren, TagDefinition},
     modules, parser,
     spel::{
         self,
@@ -21,12 +27,22 @@ use crate::{
     },
 };
 
-use super::LsError;
+use super::{symbol, LsError};
 
 pub(crate) struct DiagnosticCollector {
     pub(crate) file: Url,
     pub(crate) text: String,
     pub(crate) diagnostics: Vec<Diagnostic>,
+    warn_unused_variables: bool,
+    warn_undefined_variables: bool,
+    strict_html: bool,
+    missing_content_type: Option<DiagnosticSeverity>,
+    content_type: String,
+    /**
+     * without any module file every module is unknown, so hinting at them would only be noise.
+     */
+    has_module_files: bool,
+    defined_variables: HashSet<String>,
 }
 
 impl DiagnosticCollector {
@@ -35,6 +51,13 @@ impl DiagnosticCollector {
             file,
             text,
             diagnostics: Vec::new(),
+            warn_unused_variables: config::get().warn_unused_variables,
+            warn_undefined_variables: config::get().warn_undefined_variables,
+            strict_html: config::get().strict_html,
+            missing_content_type: config::get().missing_content_type,
+            content_type: config::get().content_type.clone(),
+            has_module_files: !modules::module_files().is_empty(),
+            defined_variables: HashSet::new(),
         };
     }
 
@@ -48,10 +71,12 @@ impl DiagnosticCollector {
             match node.kind() {
                 "page_header" | "import_header" | "taglib_header" | "html_doctype" | "text"
                 | "comment" | "xml_entity" => continue,
+                "ERROR" if parser::is_cdata(node, &self.text) => continue,
                 "ERROR" => self.add_diagnostic(
                     format!("unexpected \"{}\"", node.utf8_text(self.text.as_bytes())?),
                     DiagnosticSeverity::ERROR,
                     self.node_range(&node),
+                    DiagnosticType::SyntaxError,
                 ),
                 "html_tag" | "html_option_tag" | "html_void_tag" | "xml_comment" | "java_tag"
                 | "script_tag" | "style_tag" => self.validate_children(&node, spel)?,
@@ -68,16 +93,328 @@ impl DiagnosticCollector {
                 }?,
             }
         }
+        if root.kind() == "document" {
+            self.validate_taglib_prefixes(root)?;
+            self.validate_overwritten_variables(root, &mut HashSet::new())?;
+            self.validate_timestamp_connections(root)?;
+        }
+        if self.warn_unused_variables {
+            self.validate_unused_variables(root, spel)?;
+        }
+        self.apply_suppressions(root)?;
+        return Ok(());
+    }
+
+    /**
+     * drops diagnostics disabled by comments. `<%-- lspml-disable --%>` disables all diagnostics
+     * on the following line, `<%-- lspml-disable-line --%>` those on its own line. both may be
+     * followed by diagnostic tags like `deprecated` or types like `SYNTAX_ERROR` to only disable
+     * matching diagnostics. unknown names are hinted at and disable nothing.
+     */
+    fn apply_suppressions(&mut self, root: &Node) -> Result<()> {
+        let mut comments = Vec::new();
+        collect_comments(root, &mut comments);
+        let mut suppressions: Vec<(u32, Option<Vec<config::DiagnosticSelector>>)> = Vec::new();
+        let mut unknown = Vec::new();
+        for comment in comments {
+            let content = comment.utf8_text(self.text.as_bytes())?;
+            let mut words = content
+                .trim_start_matches("<%--")
+                .trim_end_matches("--%>")
+                .split_whitespace()
+                .peekable();
+            let line = match words.next() {
+                Some("lspml-disable") => comment.end_position().row + 1,
+                Some("lspml-disable-line") => comment.start_position().row,
+                _ => continue,
+            };
+            if words.peek().is_none() {
+                suppressions.push((line as u32, None));
+                continue;
+            }
+            let mut selectors = Vec::new();
+            for word in words {
+                match config::parse_diagnostic_selector(word) {
+                    Ok(selector) => selectors.push(selector),
+                    Err(err) => unknown.push((err.to_string(), self.node_range(&comment))),
+                }
+            }
+            suppressions.push((line as u32, Some(selectors)));
+        }
+        self.diagnostics.retain(|diagnostic| {
+            return !suppressions.iter().any(|(line, selectors)| {
+                *line == diagnostic.range.start.line
+                    && selectors.as_ref().is_none_or(|selectors| {
+                        selectors
+                            .iter()
+                            .any(|selector| selector.matches(diagnostic))
+                    })
+            });
+        });
+        for (message, range) in unknown {
+            self.add_diagnostic(
+                message,
+                DiagnosticSeverity::HINT,
+                range,
+                DiagnosticType::InvalidSuppression,
+            );
+        }
+        return Ok(());
+    }
+
+    /**
+     * hints at variables declared with sp:set or sp:for that are never referenced in this
+     * document. variables set in the request scope may be used in other files and are ignored.
+     */
+    fn validate_unused_variables(
+        &mut self,
+        root: &Node,
+        spel: &HashMap<Point, SpelAst>,
+    ) -> Result<()> {
+        let mut declarations = Vec::new();
+        self.collect_declarations(root, &mut declarations)?;
+        let declaration_positions: HashSet<Point> = declarations
+            .iter()
+            .map(|declaration: &Node| declaration.start_position())
+            .collect();
+        let mut references = HashMap::new();
+        for (position, ast) in spel {
+            if !declaration_positions.contains(position) {
+                collect_referenced_names(ast, &mut references);
+            }
+        }
+        for declaration in declarations {
+            let name = declaration.utf8_text(self.text.as_bytes())?;
+            if !references.contains_key(name)
+                && find_interpolations_in_text(&self.text, name).is_empty()
+            {
+                self.add_diagnostic_with_tag(
+                    format!("variable {} is never used", name),
+                    DiagnosticSeverity::HINT,
+                    self.node_range(&declaration),
+                    DiagnosticTag::UNNECESSARY,
+                    DiagnosticType::UnusedVariable,
+                );
+            }
+        }
+        return Ok(());
+    }
+
+    fn collect_declarations<'a>(
+        &self,
+        node: &Node<'a>,
+        declarations: &mut Vec<Node<'a>>,
+    ) -> Result<()> {
+        for child in node.children(&mut node.walk()) {
+            let attribute = match child.kind() {
+                "set_tag" => Some("name"),
+                "for_tag" => Some("index"),
+                _ => None,
+            };
+            if let Some(attribute) = attribute {
+                let mut name = None;
+                let mut scope = None;
+                for tag_child in child.children(&mut child.walk()) {
+                    match parser::attribute_name_of(tag_child, &self.text) {
+                        Some("scope") => scope = parser::attribute_value_of(tag_child, &self.text),
+                        Some(current) if current == attribute => {
+                            name = tag_child
+                                .child(2)
+                                .and_then(|string| string.child(1))
+                                .filter(|value| value.kind() == "string_content");
+                        }
+                        _ => {}
+                    }
+                }
+                if let Some(name) = name.filter(|_| scope != Some("request")) {
+                    let value = name.utf8_text(self.text.as_bytes())?;
+                    if !value.contains("${") {
+                        declarations.push(name);
+                    }
+                }
+            }
+            self.collect_declarations(&child, declarations)?;
+        }
+        return Ok(());
+    }
+
+    /**
+     * hints at sp:set tags that set a variable outside of the page scope, which has already been
+     * set in the same scope before, without `overwrite="true"`. sets that only insert into or
+     * default the variable are ignored, as are sets in sibling branches of an sp:if.
+     */
+    fn validate_overwritten_variables(
+        &mut self,
+        node: &Node,
+        variables: &mut HashSet<(String, String)>,
+    ) -> Result<()> {
+        // variables set in the branches of the preceding sp:if, sp:elseif and sp:else tags
+        let mut branches = HashSet::new();
+        for child in node.children(&mut node.walk()) {
+            match child.kind() {
+                "if_tag" | "elseif_tag" | "else_tag" => {
+                    if child.kind() == "if_tag" {
+                        variables.extend(branches.drain());
+                    }
+                    let mut branch = variables.clone();
+                    self.validate_overwritten_variables(&child, &mut branch)?;
+                    branches.extend(
+                        branch
+                            .into_iter()
+                            .filter(|variable| !variables.contains(variable)),
+                    );
+                    continue;
+                }
+                "text" | "comment" => {}
+                _ => variables.extend(branches.drain()),
+            }
+            if child.kind() == "set_tag" {
+                let mut name = None;
+                let mut scope = "page";
+                let mut overwrite = false;
+                let mut inserts = false;
+                let mut replaces = false;
+                let mut has_default = false;
+                for tag_child in child.children(&mut child.walk()) {
+                    let value = parser::attribute_value_of(tag_child, &self.text);
+                    match parser::attribute_name_of(tag_child, &self.text) {
+                        Some("name") => name = value,
+                        Some("scope") => scope = value.unwrap_or(scope),
+                        Some("overwrite") => overwrite = value == Some("true"),
+                        Some("insert") => inserts = true,
+                        Some("default") => has_default = true,
+                        Some("value" | "expression" | "condition" | "object") => replaces = true,
+                        _ => {}
+                    }
+                }
+                let only_default = has_default && !replaces;
+                if let Some(name) = name.filter(|name| !name.contains("${") && scope != "page") {
+                    let variable = (name.to_string(), scope.to_string());
+                    if variables.contains(&variable) && !overwrite && !inserts && !only_default {
+                        self.add_diagnostic(
+                            format!(
+                                "variable {} is already set in the {} scope and may not be \
+                                 replaced without overwrite=\"true\"",
+                                name, scope
+                            ),
+                            DiagnosticSeverity::HINT,
+                            self.node_tag_range(&child),
+                            DiagnosticType::OverwrittenVariable,
+                        );
+                    }
+                    variables.insert(variable);
+                }
+            }
+            self.validate_overwritten_variables(&child, variables)?;
+        }
+        variables.extend(branches);
         return Ok(());
     }
 
+    /**
+     * hints at `spt:timestamp` tags whose `connect` attribute names no input field of the
+     * document. fields may be declared before or after the timestamp.
+     */
+    fn validate_timestamp_connections(&mut self, root: &Node) -> Result<()> {
+        let text = self.text.clone();
+        let mut fields = HashSet::new();
+        let mut connections = Vec::new();
+        collect_field_names(root, &text, &mut fields, &mut connections);
+        for (connect, attribute) in connections {
+            if !connect.contains("${") && !fields.contains(connect) {
+                self.add_diagnostic(
+                    format!(
+                        "no input field named \"{}\" found in this document",
+                        connect
+                    ),
+                    DiagnosticSeverity::HINT,
+                    self.node_range(&attribute),
+                    DiagnosticType::UnknownInputField,
+                );
+            }
+        }
+        return Ok(());
+    }
+
+    /**
+     * reports namespaced tags like `<x:foo>` whose prefix is not declared by any taglib header
+     * and taglib headers whose prefix is never used.
+     */
+    fn validate_taglib_prefixes(&mut self, root: &Node) -> Result<()> {
+        let headers = self.collect_taglib_headers(root);
+        let mut tags = Vec::new();
+        collect_namespaced_tags(root, &self.text, &mut tags);
+        let header_end = root
+            .children(&mut root.walk())
+            .filter(|node| node.kind().ends_with("_header"))
+            .last()
+            .map(|header| self.node_range(&header).end);
+        for (prefix, tag) in &tags {
+            if !headers.iter().any(|(declared, _)| declared == prefix) {
+                let import = grammar::taglib_import(prefix)
+                    .zip(header_end)
+                    .map(|(import, end)| TextEdit {
+                        range: Range { start: end, end },
+                        new_text: import.to_string(),
+                    });
+                self.diagnostics.push(Diagnostic {
+                    message: format!("taglib prefix \"{}\" is not declared", prefix),
+                    severity: Some(DiagnosticSeverity::WARNING),
+                    range: self.node_range(tag),
+                    source: Some(String::from("lspml")),
+                    code: Some(DiagnosticType::UndeclaredTaglib.code()),
+                    data: import.and_then(|import| serde_json::to_value(vec![import]).ok()),
+                    ..Default::default()
+                });
+            }
+        }
+        for (prefix, header) in &headers {
+            if !tags.iter().any(|(used, _)| used == prefix) {
+                self.diagnostics.push(Diagnostic {
+                    message: format!("taglib prefix \"{}\" is never used", prefix),
+                    severity: Some(DiagnosticSeverity::HINT),
+                    range: self.node_range(header),
+                    source: Some(String::from("lspml")),
+                    tags: Some(vec![DiagnosticTag::UNNECESSARY]),
+                    code: Some(DiagnosticType::UnusedTaglib.code()),
+                    data: serde_json::to_value(vec![TextEdit {
+                        range: self.node_range(header),
+                        new_text: String::new(),
+                    }])
+                    .ok(),
+                    ..Default::default()
+                });
+            }
+        }
+        return Ok(());
+    }
+
+    fn collect_taglib_headers<'a>(&self, root: &Node<'a>) -> Vec<(String, Node<'a>)> {
+        let mut headers = Vec::new();
+        for node in root.children(&mut root.walk()) {
+            if node.kind() != "taglib_header" {
+                continue;
+            }
+            let prefix = node
+                .utf8_text(self.text.as_bytes())
+                .ok()
+                .and_then(|header| header.split_once("prefix=\""))
+                .and_then(|(_, rest)| rest.split_once('"'))
+                .map(|(prefix, _)| prefix.to_string());
+            if let Some(prefix) = prefix {
+                headers.push((prefix, node));
+            }
+        }
+        return headers;
+    }
+
     fn validate_header(&mut self, root: &Node) -> Result<()> {
         if root.kind() != "document" {
             let document_start = Position {
                 line: 0,
                 character: 0,
             };
-            self.add_diagnostic_with_code(
+            self.add_diagnostic_with_data(
                 format!(
                     "missing atleast one header. Try generating one with the \"{}\" code-action",
                     CodeActionImplementation::GenerateDefaultHeaders
@@ -87,10 +424,70 @@ impl DiagnosticCollector {
                     start: document_start,
                     end: document_start,
                 },
-                CodeActionImplementation::GENERATE_DEFAULT_HEADER_CODE,
+                DiagnosticType::MissingHeader,
                 None,
             );
+            return Ok(());
+        }
+        let children: Vec<Node> = root.children(&mut root.walk()).collect();
+        let last_header = children
+            .iter()
+            .rposition(|node| node.kind().ends_with("_header"));
+        if let Some(last_header) = last_header {
+            for node in &children[..last_header] {
+                if node.kind().ends_with("_header")
+                    || node.kind() == "comment"
+                    || node.kind() == "xml_comment"
+                    || node.utf8_text(self.text.as_bytes())?.trim().is_empty()
+                {
+                    continue;
+                }
+                self.add_diagnostic(
+                    "headers have to be placed before any other content".to_string(),
+                    DiagnosticSeverity::ERROR,
+                    self.node_range(node),
+                    DiagnosticType::MisplacedHeader,
+                );
+            }
+        }
+        if let Some(severity) = self.missing_content_type {
+            for header in children.iter().filter(|node| node.kind() == "page_header") {
+                self.validate_content_type(header, severity)?;
+            }
+        }
+        return Ok(());
+    }
+
+    /**
+     * a page header without a contentType leads to the response being sent with the default
+     * charset of the server, which rarely is the intended one.
+     */
+    fn validate_content_type(&mut self, header: &Node, severity: DiagnosticSeverity) -> Result<()> {
+        let text = header.utf8_text(self.text.as_bytes())?;
+        if text.contains("contentType=") {
+            return Ok(());
         }
+        let insert = match text.find("page") {
+            Some(index) => Position {
+                line: header.start_position().row as u32,
+                character: (header.start_position().column + index + "page".len()) as u32,
+            },
+            None => return Ok(()),
+        };
+        self.add_diagnostic_with_data(
+            "page header is missing a contentType".to_string(),
+            severity,
+            self.node_range(header),
+            DiagnosticType::MissingContentType,
+            serde_json::to_value(vec![TextEdit {
+                range: Range {
+                    start: insert,
+                    end: insert,
+                },
+                new_text: format!(" contentType=\"{}\"", self.content_type),
+            }])
+            .ok(),
+        );
         return Ok(());
     }
 
@@ -100,30 +497,71 @@ impl DiagnosticCollector {
         node: &Node,
         spel: &HashMap<Point, SpelAst>,
     ) -> Result<()> {
-        if tag.deprecated {
-            self.add_diagnostic_with_tag(
-                format!("{} tag is deprecated", tag.name),
-                DiagnosticSeverity::INFORMATION,
+        if tag.name == "sp:option" && !has_ancestor(node, "select_tag") {
+            self.add_diagnostic(
+                "sp:option tag should be inside of an sp:select tag".to_string(),
+                DiagnosticSeverity::WARNING,
                 self.node_tag_range(node),
-                DiagnosticTag::DEPRECATED,
+                DiagnosticType::MisplacedTag,
             );
         }
+        if tag.deprecated {
+            self.diagnostics.push(Diagnostic {
+                message: format!("{} tag is deprecated", tag.name),
+                severity: Some(DiagnosticSeverity::INFORMATION),
+                range: self.node_tag_range(node),
+                source: Some(String::from("lspml")),
+                tags: Some(vec![DiagnosticTag::DEPRECATED]),
+                code: Some(DiagnosticType::DeprecatedTag.code()),
+                data: serde_json::to_value(vec![TextEdit {
+                    range: self.node_range(node),
+                    new_text: String::new(),
+                }])
+                .ok(),
+                ..Default::default()
+            });
+        }
         let mut attributes: HashMap<String, String> = HashMap::new();
+        let mut attribute_ranges: HashMap<String, Range> = HashMap::new();
+        let mut declared_variables = Vec::new();
         for child in node.children(&mut node.walk()) {
+            // variables declared by this tag are only known after all of its attributes
+            if !child.kind().ends_with("_attribute") {
+                self.defined_variables.extend(declared_variables.drain(..));
+            }
             match child.kind() {
                 // may need to check on kind of missing child
                 _ if child.is_missing() => self.add_diagnostic(
                     format!("{} is never closed", node.kind()),
                     DiagnosticSeverity::ERROR,
                     self.node_tag_range(node),
+                    DiagnosticType::SyntaxError,
                 ),
-                _ if child.is_error() => self.add_diagnostic(
-                    format!("unexpected \"{}\"", child.utf8_text(self.text.as_bytes())?),
-                    DiagnosticSeverity::ERROR,
-                    self.node_range(&child),
-                ),
-                "html_void_tag" | "java_tag" | "script_tag" | "style_tag" => {}
-                "html_tag" | "html_option_tag" => self.validate_children(&child, spel)?,
+                _ if parser::is_cdata(child, &self.text) => {}
+                _ if child.is_error() => match truncated_attribute(&child, &self.text) {
+                    Some(attribute) => self.validate_unescaped_quote(&attribute),
+                    None => self.add_diagnostic(
+                        format!("unexpected \"{}\"", child.utf8_text(self.text.as_bytes())?),
+                        DiagnosticSeverity::ERROR,
+                        self.node_range(&child),
+                        DiagnosticType::SyntaxError,
+                    ),
+                },
+                "text" | "html_tag" | "html_void_tag" | "html_option_tag"
+                    if matches!(tag.children, TagChildren::Vector(_))
+                        && !child.utf8_text(self.text.as_bytes())?.trim().is_empty() =>
+                {
+                    self.add_diagnostic(
+                        format!("unexpected content in {} tag", tag.name),
+                        DiagnosticSeverity::WARNING,
+                        self.node_range(&child),
+                        DiagnosticType::InvalidTagBody,
+                    )
+                }
+                "html_void_tag" | "java_tag" => {}
+                "html_tag" | "html_option_tag" | "script_tag" | "style_tag" => {
+                    self.validate_children(&child, spel)?
+                }
                 kind if kind.ends_with("_attribute") => {
                     let (attribute, value) =
                         match parser::attribute_name_and_value_of(child, self.text.as_str()) {
@@ -133,13 +571,24 @@ impl DiagnosticCollector {
                     if let Some(value_node) = child.child(2).and_then(|child| child.child(1)) {
                         SpelValidator::validate(self, &value_node, spel)?;
                     };
+                    if let Some(TagAttributeType::Identifier) = tag
+                        .attributes
+                        .get_by_name(&attribute)
+                        .map(|attribute| &attribute.r#type)
+                    {
+                        if !value.contains("${") {
+                            declared_variables.push(value.clone());
+                        }
+                    }
                     if attributes.contains_key(&attribute) {
                         self.add_diagnostic(
                             format!("duplicate {} attribute", attribute),
                             DiagnosticSeverity::WARNING,
                             self.node_tag_range(node),
+                            DiagnosticType::InvalidAttributes,
                         );
                     } else {
+                        attribute_ranges.insert(attribute.clone(), self.node_range(&child));
                         attributes.insert(attribute, value);
                     }
                 }
@@ -151,6 +600,7 @@ impl DiagnosticCollector {
                         format!("unexpected {} tag", &kind[..kind.find("_tag").unwrap()]),
                         DiagnosticSeverity::WARNING,
                         self.node_range(&child),
+                        DiagnosticType::MisplacedTag,
                     ),
                     Err(err) => log::info!("expected sp or spt tag: {}", err),
                 },
@@ -165,6 +615,7 @@ impl DiagnosticCollector {
                         DiagnosticSeverity::INFORMATION,
                         self.node_tag_range(node),
                         DiagnosticTag::DEPRECATED,
+                        DiagnosticType::DeprecatedAttribute,
                     );
                 }
                 grammar::AttributeRule::AtleastOneOf(names)
@@ -177,6 +628,7 @@ impl DiagnosticCollector {
                         ),
                         DiagnosticSeverity::ERROR,
                         self.node_tag_range(node),
+                        DiagnosticType::InvalidAttributes,
                     );
                 }
                 grammar::AttributeRule::ExactlyOneOf(names) => {
@@ -190,6 +642,7 @@ impl DiagnosticCollector {
                             format!("requires one of these attributes: {}", names.join(", ")),
                             DiagnosticSeverity::ERROR,
                             self.node_tag_range(node),
+                            DiagnosticType::InvalidAttributes,
                         ),
                         1 => {}
                         _ => self.add_diagnostic(
@@ -199,6 +652,7 @@ impl DiagnosticCollector {
                             ),
                             DiagnosticSeverity::ERROR,
                             self.node_tag_range(node),
+                            DiagnosticType::InvalidAttributes,
                         ),
                     }
                 }
@@ -219,6 +673,7 @@ impl DiagnosticCollector {
                             ),
                             DiagnosticSeverity::ERROR,
                             self.node_tag_range(node),
+                            DiagnosticType::InvalidAttributes,
                         ),
                         (0, true) | (1, false) => {}
                         _ => self.add_diagnostic(
@@ -228,6 +683,7 @@ impl DiagnosticCollector {
                             ),
                             DiagnosticSeverity::ERROR,
                             self.node_tag_range(node),
+                            DiagnosticType::InvalidAttributes,
                         ),
                     }
                 }
@@ -241,6 +697,7 @@ impl DiagnosticCollector {
                         format!("requires either a tag-body or the attribute {}", name,),
                         DiagnosticSeverity::ERROR,
                         self.node_tag_range(node),
+                        DiagnosticType::InvalidAttributes,
                     );
                 }
                 grammar::AttributeRule::OnlyOneOf(names) => {
@@ -257,9 +714,23 @@ impl DiagnosticCollector {
                             ),
                             DiagnosticSeverity::WARNING,
                             self.node_tag_range(node),
+                            DiagnosticType::InvalidAttributes,
                         );
                     }
                 }
+                grammar::AttributeRule::Conflicts(first, second)
+                    if attributes.contains_key(*first) && attributes.contains_key(*second) =>
+                {
+                    self.add_diagnostic(
+                        format!(
+                            "attribute {} cannot be combined with attribute {}",
+                            first, second
+                        ),
+                        DiagnosticSeverity::WARNING,
+                        self.node_tag_range(node),
+                        DiagnosticType::InvalidAttributes,
+                    );
+                }
                 grammar::AttributeRule::OnlyOneOfOrBody(names) => {
                     let present: Vec<&str> = names
                         .iter()
@@ -276,7 +747,11 @@ impl DiagnosticCollector {
                                 present.join(", ")
                             ),
                             DiagnosticSeverity::WARNING,
-                            self.node_tag_range(node),
+                            match present[..] {
+                                [attribute] => attribute_ranges[attribute],
+                                _ => self.node_tag_range(node),
+                            },
+                            DiagnosticType::InvalidAttributes,
                         ),
                         (len, false) if len > 1 => self.add_diagnostic(
                             format!(
@@ -285,6 +760,7 @@ impl DiagnosticCollector {
                             ),
                             DiagnosticSeverity::WARNING,
                             self.node_tag_range(node),
+                            DiagnosticType::InvalidAttributes,
                         ),
                         _ => {}
                     }
@@ -298,7 +774,8 @@ impl DiagnosticCollector {
                     self.add_diagnostic(
                         format!("can only have either a tag-body or the {} attribute", name),
                         DiagnosticSeverity::WARNING,
-                        self.node_tag_range(node),
+                        attribute_ranges[*name],
+                        DiagnosticType::InvalidAttributes,
                     );
                 }
                 grammar::AttributeRule::OnlyWith(name1, name2)
@@ -307,7 +784,8 @@ impl DiagnosticCollector {
                     self.add_diagnostic(
                         format!("attribute {} is useless without attribute {}", name1, name2),
                         DiagnosticSeverity::WARNING,
-                        self.node_tag_range(node),
+                        attribute_ranges[*name1],
+                        DiagnosticType::InvalidAttributes,
                     );
                 }
                 grammar::AttributeRule::OnlyWithEither(name, names)
@@ -321,7 +799,8 @@ impl DiagnosticCollector {
                             names.join(", ")
                         ),
                         DiagnosticSeverity::WARNING,
-                        self.node_tag_range(node),
+                        attribute_ranges[*name],
+                        DiagnosticType::InvalidAttributes,
                     );
                 }
                 grammar::AttributeRule::OnlyWithEitherOrBody(name, names)
@@ -338,7 +817,8 @@ impl DiagnosticCollector {
                             names.join(", ")
                         ),
                         DiagnosticSeverity::WARNING,
-                        self.node_tag_range(node),
+                        attribute_ranges[*name],
+                        DiagnosticType::InvalidAttributes,
                     );
                 }
                 grammar::AttributeRule::Required(name) if !attributes.contains_key(*name) => {
@@ -346,22 +826,110 @@ impl DiagnosticCollector {
                         format!("missing required attribute {}", name),
                         DiagnosticSeverity::ERROR,
                         self.node_tag_range(node),
+                        DiagnosticType::InvalidAttributes,
+                    );
+                }
+                grammar::AttributeRule::LocaleValue(name)
+                    if attributes
+                        .get(*name)
+                        .is_some_and(|v| !v.contains("${") && !is_locale(v)) =>
+                {
+                    self.add_diagnostic(
+                        format!(
+                            "attribute {} should be a locale like \"de\" or \"en_US\"",
+                            name
+                        ),
+                        DiagnosticSeverity::WARNING,
+                        self.node_tag_range(node),
+                        DiagnosticType::InvalidAttributeValue,
+                    );
+                }
+                grammar::AttributeRule::NonZeroValue(name)
+                    if attributes
+                        .get(*name)
+                        .is_some_and(|v| v.trim().parse::<f64>().is_ok_and(|v| v == 0.0)) =>
+                {
+                    self.add_diagnostic(
+                        format!("attribute {} must not be 0", name),
+                        DiagnosticSeverity::ERROR,
+                        self.node_tag_range(node),
+                        DiagnosticType::InvalidAttributeValue,
+                    );
+                }
+                grammar::AttributeRule::IdentifierValue(name)
+                    if attributes
+                        .get(*name)
+                        .is_some_and(|v| !v.contains("${") && !is_identifier(v)) =>
+                {
+                    self.add_diagnostic(
+                        format!("attribute {} should be a valid variable name", name),
+                        DiagnosticSeverity::ERROR,
+                        self.node_tag_range(node),
+                        DiagnosticType::InvalidAttributeValue,
+                    );
+                }
+                grammar::AttributeRule::RequiredBody
+                    if !node
+                        .child(node.child_count() - 1)
+                        .is_some_and(|tag| tag.kind().ends_with("_tag_close")) =>
+                {
+                    self.add_diagnostic(
+                        format!("{} tag requires a tag-body", tag.name),
+                        DiagnosticSeverity::WARNING,
+                        self.node_tag_range(node),
+                        DiagnosticType::InvalidTagBody,
                     );
                 }
+                grammar::AttributeRule::NonEmptyBody if has_empty_body(node, &self.text) => {
+                    self.add_diagnostic_with_tag(
+                        format!("{} tag has an empty tag-body", tag.name),
+                        DiagnosticSeverity::HINT,
+                        self.node_range(node),
+                        DiagnosticTag::UNNECESSARY,
+                        DiagnosticType::InvalidTagBody,
+                    );
+                }
+                grammar::AttributeRule::DateFormatValue(name) => {
+                    if let Some(error) = attributes
+                        .get(*name)
+                        .filter(|value| !value.contains("${"))
+                        .and_then(|value| date_format_error(value))
+                    {
+                        self.add_diagnostic(
+                            format!("attribute {} is not a valid date format: {}", name, error),
+                            DiagnosticSeverity::ERROR,
+                            self.node_tag_range(node),
+                            DiagnosticType::InvalidAttributeValue,
+                        );
+                    }
+                }
+                grammar::AttributeRule::DecimalFormatValue(name) => {
+                    if let Some(error) = attributes
+                        .get(*name)
+                        .filter(|value| !value.contains("${"))
+                        .and_then(|value| decimal_format_error(value))
+                    {
+                        self.add_diagnostic(
+                            format!(
+                                "attribute {} is not a valid decimal format: {}",
+                                name, error
+                            ),
+                            DiagnosticSeverity::ERROR,
+                            self.node_tag_range(node),
+                            DiagnosticType::InvalidAttributeValue,
+                        );
+                    }
+                }
                 grammar::AttributeRule::UriExists(uri_name, module_name) => {
                     if let Some(uri) = attributes.get(*uri_name) {
                         if uri.contains("${") {
                             continue;
                         }
                         let module_value = attributes.get(*module_name).map(|str| str.as_str());
-                        let module = match module_value {
-                            Some("${module.id}") | None => self
-                                .file
-                                .to_file_path()
-                                .ok()
-                                .and_then(|file| modules::find_module_for_file(file.as_path())),
-                            Some(module) => modules::find_module_by_name(module),
-                        };
+                        let module =
+                            self.file.to_file_path().ok().and_then(|file| {
+                                modules::resolve_module(module_value, file.as_path())
+                            });
                         match module {
                             Some(module) => {
                                 let file = format!("{}{}", module.path, uri);
@@ -370,24 +938,38 @@ impl DiagnosticCollector {
                                         format!("included file {} does not exist", file),
                                         DiagnosticSeverity::ERROR,
                                         self.node_tag_range(node),
+                                        DiagnosticType::MissingFile,
                                     );
                                 }
                             }
+                            // unknown named modules are reported by ModuleExists
+                            None if module_value.is_some_and(|module| module != "${module.id}") => {
+                            }
+                            None if !self.has_module_files => {}
                             None => self.add_diagnostic(
-                                match module_value {
-                                    Some("${module.id}") | None => {
-                                        "current module not listed in module-file".to_string()
-                                    }
-                                    Some(name) => {
-                                        format!("module \"{}\" not listed in module-file", name)
-                                    }
-                                },
+                                "current module not listed in module-file".to_string(),
                                 DiagnosticSeverity::HINT,
                                 self.node_tag_range(node),
+                                DiagnosticType::UnknownModule,
                             ),
                         }
                     }
                 }
+                grammar::AttributeRule::ModuleExists(name) => {
+                    if let Some(module) = attributes.get(*name) {
+                        if self.has_module_files
+                            && !module.contains("${")
+                            && modules::find_module_by_name(module).is_none()
+                        {
+                            self.add_diagnostic(
+                                format!("module \"{}\" not listed in module-file", module),
+                                DiagnosticSeverity::HINT,
+                                self.node_tag_range(node),
+                                DiagnosticType::UnknownModule,
+                            );
+                        }
+                    }
+                }
                 grammar::AttributeRule::ValueOneOf(name, values)
                     if attributes
                         .get(*name)
@@ -401,6 +983,7 @@ impl DiagnosticCollector {
                         ),
                         DiagnosticSeverity::ERROR,
                         self.node_tag_range(node),
+                        DiagnosticType::InvalidAttributeValue,
                     );
                 }
                 grammar::AttributeRule::ValueOneOfCaseInsensitive(name, values)
@@ -416,6 +999,7 @@ impl DiagnosticCollector {
                         ),
                         DiagnosticSeverity::ERROR,
                         self.node_tag_range(node),
+                        DiagnosticType::InvalidAttributeValue,
                     );
                 }
                 grammar::AttributeRule::OnlyWithValue(name, attribute, value)
@@ -428,7 +1012,8 @@ impl DiagnosticCollector {
                             name, attribute, value
                         ),
                         DiagnosticSeverity::WARNING,
-                        self.node_tag_range(node),
+                        attribute_ranges[*name],
+                        DiagnosticType::InvalidAttributes,
                     );
                 }
                 grammar::AttributeRule::OnlyWithEitherValue(name, attribute, values)
@@ -443,7 +1028,8 @@ impl DiagnosticCollector {
                             name, attribute, values.join(", ")
                         ),
                         DiagnosticSeverity::WARNING,
-                        self.node_tag_range(node),
+                        attribute_ranges[*name],
+                        DiagnosticType::InvalidAttributes,
                     );
                 }
                 grammar::AttributeRule::BodyOnlyWithEitherValue(attribute, values)
@@ -461,6 +1047,7 @@ impl DiagnosticCollector {
                         ),
                         DiagnosticSeverity::WARNING,
                         self.node_tag_range(node),
+                        DiagnosticType::InvalidTagBody,
                     );
                 }
                 grammar::AttributeRule::RequiredWithValue(name, attribute, value)
@@ -474,6 +1061,7 @@ impl DiagnosticCollector {
                         ),
                         DiagnosticSeverity::ERROR,
                         self.node_tag_range(node),
+                        DiagnosticType::InvalidAttributes,
                     );
                 }
                 grammar::AttributeRule::RequiredOrBodyWithValue(name, attribute, value)
@@ -493,6 +1081,7 @@ impl DiagnosticCollector {
                             ),
                             DiagnosticSeverity::ERROR,
                             self.node_tag_range(node),
+                            DiagnosticType::InvalidAttributes,
                         ),
                         (true, true) => self.add_diagnostic(
                             format!(
@@ -503,6 +1092,7 @@ impl DiagnosticCollector {
                             ),
                             DiagnosticSeverity::ERROR,
                             self.node_range(node),
+                            DiagnosticType::InvalidAttributes,
                         ),
                         _ => {}
                     }
@@ -522,6 +1112,7 @@ impl DiagnosticCollector {
                         ),
                         DiagnosticSeverity::ERROR,
                         self.node_tag_range(node),
+                        DiagnosticType::InvalidAttributes,
                     );
                 }
                 grammar::AttributeRule::ExactlyOneOfOrBodyWithValue(names, attribute, value)
@@ -544,6 +1135,7 @@ impl DiagnosticCollector {
                                 ),
                                 DiagnosticSeverity::ERROR,
                                 self.node_tag_range(node),
+                                DiagnosticType::InvalidAttributes,
                             );
                         }
                         (0, true) | (1, false) => {}
@@ -554,6 +1146,7 @@ impl DiagnosticCollector {
                             ),
                             DiagnosticSeverity::ERROR,
                             self.node_tag_range(node),
+                            DiagnosticType::InvalidAttributes,
                         ),
                     }
                 }
@@ -581,6 +1174,7 @@ impl DiagnosticCollector {
                             ),
                             DiagnosticSeverity::ERROR,
                             self.node_tag_range(node),
+                            DiagnosticType::InvalidAttributes,
                         ),
                         (0, true) | (1, false) => {}
                         _ => self.add_diagnostic(
@@ -590,6 +1184,7 @@ impl DiagnosticCollector {
                             ),
                             DiagnosticSeverity::ERROR,
                             self.node_tag_range(node),
+                            DiagnosticType::InvalidAttributes,
                         )
                     }
                 }
@@ -609,12 +1204,29 @@ impl DiagnosticCollector {
     }
 
     fn validate_children(&mut self, node: &Node, spel: &HashMap<Point, SpelAst>) -> Result<()> {
+        if node.kind() == "html_tag" {
+            self.validate_html_tag_names(node)?;
+        }
+        if self.strict_html && node.kind().starts_with("html_") {
+            self.validate_known_html_tag(node)?;
+        }
         for child in node.children(&mut node.walk()) {
             match child.kind() {
+                "ERROR" if parser::is_cdata(child, &self.text) => {}
+                "ERROR" if is_embedded_spml_error(&child, &self.text) => self.add_diagnostic(
+                    format!(
+                        "incomplete spml tag inside {} body. Escape \"<\" or move the tag out",
+                        embedding_tag_name(&child),
+                    ),
+                    DiagnosticSeverity::ERROR,
+                    self.node_range(&child),
+                    DiagnosticType::SyntaxError,
+                ),
                 "ERROR" => self.add_diagnostic(
                     format!("unexpected \"{}\"", child.utf8_text(self.text.as_bytes())?),
                     DiagnosticSeverity::ERROR,
                     self.node_range(&child),
+                    DiagnosticType::SyntaxError,
                 ),
                 "text" => {
                     // TODO: what tags can/cannot have text?
@@ -635,12 +1247,141 @@ impl DiagnosticCollector {
         return Ok(());
     }
 
-    fn add_diagnostic(&mut self, message: String, severity: DiagnosticSeverity, range: Range) {
+    fn validate_html_tag_names(&mut self, node: &Node) -> Result<()> {
+        let mut open = None;
+        let mut close = None;
+        for child in node.children(&mut node.walk()) {
+            match child.kind() {
+                // "<div"
+                kind if kind.ends_with("_tag_open") => open = Some(child),
+                // "</div>", unless it is missing because the tag is never closed
+                kind if kind.ends_with("_tag_close") && !child.is_missing() => close = Some(child),
+                _ => {}
+            }
+        }
+        if let (Some(open), Some(close)) = (open, close) {
+            let open_name = &open.utf8_text(self.text.as_bytes())?[1..];
+            let close_text = close.utf8_text(self.text.as_bytes())?;
+            let (prefix, close_content) = match close_text.strip_prefix("</") {
+                Some(content) => (2, content),
+                None => (0, close_text),
+            };
+            let close_content = close_content.strip_suffix('>').unwrap_or(close_content);
+            let close_name = close_content.trim();
+            if !open_name.eq_ignore_ascii_case(close_name) {
+                let start = close.start_position();
+                let column =
+                    start.column + prefix + close_content.len() - close_content.trim_start().len();
+                let range = Range {
+                    start: Position {
+                        line: start.row as u32,
+                        character: column as u32,
+                    },
+                    end: Position {
+                        line: start.row as u32,
+                        character: (column + close_name.len()) as u32,
+                    },
+                };
+                self.add_diagnostic(
+                    format!(
+                        "closing tag \"{}\" does not match opening tag \"{}\"",
+                        close_name, open_name
+                    ),
+                    DiagnosticSeverity::ERROR,
+                    range,
+                    DiagnosticType::SyntaxError,
+                );
+            }
+        }
+        return Ok(());
+    }
+
+    /**
+     * hints at html tags that are not standard elements, like custom web components.
+     * the contents of inline svg and math elements follow their own vocabularies and are left
+     * unchecked.
+     */
+    fn validate_known_html_tag(&mut self, node: &Node) -> Result<()> {
+        let mut ancestor = node.parent();
+        while let Some(parent) = ancestor {
+            if let Some(open) = parent
+                .children(&mut parent.walk())
+                .find(|child| child.kind().ends_with("_tag_open"))
+            {
+                let name = &open.utf8_text(self.text.as_bytes())?[1..];
+                if FOREIGN_HTML_TAGS
+                    .iter()
+                    .any(|foreign| foreign.eq_ignore_ascii_case(name))
+                {
+                    return Ok(());
+                }
+            }
+            ancestor = parent.parent();
+        }
+        if let Some(open) = node
+            .children(&mut node.walk())
+            .find(|child| child.kind().ends_with("_tag_open"))
+        {
+            let name = &open.utf8_text(self.text.as_bytes())?[1..];
+            if !HTML_TAGS
+                .iter()
+                .any(|known| known.eq_ignore_ascii_case(name))
+            {
+                let mut range = self.node_range(&open);
+                range.start.character += 1;
+                self.add_diagnostic(
+                    format!("unknown html tag \"{}\"", name),
+                    DiagnosticSeverity::HINT,
+                    range,
+                    DiagnosticType::UnknownHtmlTag,
+                );
+            }
+        }
+        return Ok(());
+    }
+
+    /**
+     * points at the quote, that ended the value of the given attribute too early, and proposes
+     * to escape it.
+     */
+    fn validate_unescaped_quote(&mut self, attribute: &Node) {
+        let end = attribute.end_position();
+        let quote = Range {
+            start: Position {
+                line: end.row as u32,
+                character: end.column as u32 - 1,
+            },
+            end: Position {
+                line: end.row as u32,
+                character: end.column as u32,
+            },
+        };
+        self.add_diagnostic_with_data(
+            "unescaped quote in attribute value. Try escaping it with \\\"".to_string(),
+            DiagnosticSeverity::ERROR,
+            quote,
+            DiagnosticType::SpelSyntaxError,
+            serde_json::to_value(vec![TextEdit {
+                range: quote,
+                new_text: String::from("\\\""),
+            }])
+            .ok(),
+        );
+    }
+
+    fn add_diagnostic(
+        &mut self,
+        message: String,
+        severity: DiagnosticSeverity,
+        range: Range,
+        r#type: DiagnosticType,
+    ) {
         self.diagnostics.push(Diagnostic {
             message,
             severity: Some(severity),
             range,
             source: Some(String::from("lspml")),
+            code: Some(r#type.code()),
             ..Default::default()
         });
     }
@@ -651,23 +1392,25 @@ impl DiagnosticCollector {
         severity: DiagnosticSeverity,
         range: Range,
         tags: DiagnosticTag,
+        r#type: DiagnosticType,
     ) {
         self.diagnostics.push(Diagnostic {
             message,
             severity: Some(severity),
             range,
             source: Some(String::from("lspml")),
+            code: Some(r#type.code()),
             tags: Some(vec![tags]),
             ..Default::default()
         });
     }
 
-    fn add_diagnostic_with_code(
+    fn add_diagnostic_with_data(
         &mut self,
         message: String,
         severity: DiagnosticSeverity,
         range: Range,
-        code: NumberOrString,
+        r#type: DiagnosticType,
         data: Option<serde_json::Value>,
     ) {
         self.diagnostics.push(Diagnostic {
@@ -675,7 +1418,7 @@ impl DiagnosticCollector {
             severity: Some(severity),
             range,
             source: Some(String::from("lspml")),
-            code: Some(code),
+            code: Some(r#type.code()),
             data,
             ..Default::default()
         });
@@ -715,6 +1458,128 @@ impl DiagnosticCollector {
     }
 }
 
+const FOREIGN_HTML_TAGS: [&str; 2] = ["math", "svg"];
+
+const HTML_TAGS: [&str; 115] = [
+    "a",
+    "abbr",
+    "address",
+    "area",
+    "article",
+    "aside",
+    "audio",
+    "b",
+    "base",
+    "bdi",
+    "bdo",
+    "blockquote",
+    "body",
+    "br",
+    "button",
+    "canvas",
+    "caption",
+    "cite",
+    "code",
+    "col",
+    "colgroup",
+    "data",
+    "datalist",
+    "dd",
+    "del",
+    "details",
+    "dfn",
+    "dialog",
+    "div",
+    "dl",
+    "dt",
+    "em",
+    "embed",
+    "fieldset",
+    "figcaption",
+    "figure",
+    "footer",
+    "form",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "head",
+    "header",
+    "hgroup",
+    "hr",
+    "html",
+    "i",
+    "iframe",
+    "img",
+    "input",
+    "ins",
+    "kbd",
+    "label",
+    "legend",
+    "li",
+    "link",
+    "main",
+    "map",
+    "mark",
+    "math",
+    "menu",
+    "meta",
+    "meter",
+    "nav",
+    "noscript",
+    "object",
+    "ol",
+    "optgroup",
+    "option",
+    "output",
+    "p",
+    "param",
+    "picture",
+    "pre",
+    "progress",
+    "q",
+    "rp",
+    "rt",
+    "ruby",
+    "s",
+    "samp",
+    "script",
+    "search",
+    "section",
+    "select",
+    "slot",
+    "small",
+    "source",
+    "span",
+    "strong",
+    "style",
+    "sub",
+    "summary",
+    "sup",
+    "svg",
+    "table",
+    "tbody",
+    "td",
+    "template",
+    "textarea",
+    "tfoot",
+    "th",
+    "thead",
+    "time",
+    "title",
+    "tr",
+    "track",
+    "u",
+    "ul",
+    "var",
+    "video",
+    "wbr",
+];
+
+const IMPLICIT_OBJECTS: [&str; 5] = ["context", "publisher", "request", "session", "system"];
+
 struct SpelValidator<'a> {
     collector: &'a mut DiagnosticCollector,
     offset: Point,
@@ -748,6 +1613,9 @@ impl SpelValidator<'_> {
             ast::Object::Function(function) => self.validate_global_function(function)?,
             ast::Object::Name(name) => {
                 self.validate_interpolations_in_word(name)?;
+                if self.collector.warn_undefined_variables {
+                    self.validate_defined(name);
+                }
             }
             // ast::Object::Null(null) => todo!(),
             // ast::Object::String(string) => todo!(),
@@ -773,6 +1641,26 @@ impl SpelValidator<'_> {
         return Ok(());
     }
 
+    /**
+     * hints at names that were neither declared by a preceding tag nor are implicitly available.
+     * names passed into included files via sp:argument cannot be known here, which is why this
+     * is opt-in.
+     */
+    fn validate_defined(&mut self, name: &ast::Word) {
+        if let [ast::WordFragment::String(literal)] = name.fragments.as_slice() {
+            if !self.collector.defined_variables.contains(&literal.content)
+                && !IMPLICIT_OBJECTS.contains(&literal.content.as_str())
+            {
+                self.collector.add_diagnostic(
+                    format!("possibly undefined variable {}", literal.content),
+                    DiagnosticSeverity::HINT,
+                    self.locations_range(&literal.location, &literal.location),
+                    DiagnosticType::UndefinedVariable,
+                );
+            }
+        }
+    }
+
     fn validate_expression(&mut self, expression: &ast::Expression) -> Result<()> {
         match expression {
             // ast::Expression::Number(number) => todo!(),
@@ -825,6 +1713,14 @@ impl SpelValidator<'_> {
             ast::Condition::Comparisson { left, right, .. } => {
                 self.validate_comparable(left)?;
                 self.validate_comparable(right)?;
+                if let Some((start, end)) = mismatched_literals(left, right) {
+                    self.collector.add_diagnostic(
+                        "comparing a number with a non-numeric string".to_string(),
+                        DiagnosticSeverity::HINT,
+                        self.locations_range(start, end),
+                        DiagnosticType::MismatchedTypes,
+                    );
+                }
             }
             _ => {}
         };
@@ -858,6 +1754,7 @@ impl SpelValidator<'_> {
                             &function.name_location,
                             &function.closing_bracket_location,
                         ),
+                        DiagnosticType::InvalidFunctionCall,
                     )
                 }
                 ArgumentNumber::Exactly(number) if argument_count != number => {
@@ -871,6 +1768,7 @@ impl SpelValidator<'_> {
                             &function.name_location,
                             &function.closing_bracket_location,
                         ),
+                        DiagnosticType::InvalidFunctionCall,
                     );
                 }
                 ArgumentNumber::None if argument_count != 0 => self.collector.add_diagnostic(
@@ -883,6 +1781,7 @@ impl SpelValidator<'_> {
                         &function.name_location,
                         &function.closing_bracket_location,
                     ),
+                    DiagnosticType::InvalidFunctionCall,
                 ),
                 _ => {}
             },
@@ -890,6 +1789,7 @@ impl SpelValidator<'_> {
                 err.to_string(),
                 DiagnosticSeverity::ERROR,
                 self.locations_range(&function.name_location, &function.closing_bracket_location),
+                DiagnosticType::InvalidFunctionCall,
             ),
         }
         for argument in &function.arguments {
@@ -921,8 +1821,18 @@ impl SpelValidator<'_> {
         return Ok(());
     }
 
-    fn validate_uri(&mut self, _uri: &ast::Uri) -> Result<()> {
-        // TODO!
+    fn validate_uri(&mut self, uri: &ast::Uri) -> Result<()> {
+        match uri {
+            ast::Uri::Literal(literal) => {
+                for fragment in &literal.fragments {
+                    self.validate_interpolations_in_word(&fragment.content)?;
+                }
+                if let Some(extension) = &literal.file_extension {
+                    self.validate_interpolations_in_word(&extension.content)?;
+                }
+            }
+            ast::Uri::Object(interpolation) => self.validate_object(&interpolation.content)?,
+        };
         return Ok(());
     }
 
@@ -1003,14 +1913,15 @@ impl SpelValidator<'_> {
                 format!("invalid {}: {}", r#type, err.message),
                 DiagnosticSeverity::ERROR,
                 self.collector.node_range(node),
+                DiagnosticType::SpelSyntaxError,
             ),
             _ => {
                 let offset = node.start_position();
-                self.collector.add_diagnostic_with_code(
+                self.collector.add_diagnostic_with_data(
                     format!("invalid {}: {}", r#type, err.message),
                     DiagnosticSeverity::ERROR,
                     self.collector.node_range(node),
-                    CodeActionImplementation::FIX_SPEL_SYNTAX_CODE,
+                    DiagnosticType::SpelSyntaxError,
                     serde_json::to_value(
                         err.proposed_fixes
                             .iter()
@@ -1024,6 +1935,457 @@ impl SpelValidator<'_> {
     }
 }
 
+/**
+ * the locations of both sides of a comparisson between a number literal and a string literal,
+ * that cannot be read as a number, like `1 == 'abc'`.
+ */
+fn mismatched_literals<'a>(
+    left: &'a ast::Comparable,
+    right: &'a ast::Comparable,
+) -> Option<(&'a ast::Location, &'a ast::Location)> {
+    let is_text = |string: &ast::StringLiteral| string.content.trim().parse::<f64>().is_err();
+    return match (left, right) {
+        (
+            ast::Comparable::Expression(ast::Expression::Number(number)),
+            ast::Comparable::String(string),
+        ) if is_text(string) => Some((&number.location, &string.location)),
+        (
+            ast::Comparable::String(string),
+            ast::Comparable::Expression(ast::Expression::Number(number)),
+        ) if is_text(string) => Some((&string.location, &number.location)),
+        _ => None,
+    };
+}
+
+fn collect_comments<'a>(node: &Node<'a>, comments: &mut Vec<Node<'a>>) {
+    for child in node.children(&mut node.walk()) {
+        match child.kind() {
+            "comment" => comments.push(child),
+            _ => collect_comments(&child, comments),
+        }
+    }
+}
+
+/**
+ * the attribute directly preceding an error, that contains a quote, like in `value="a"b"`. the
+ * attribute value has most likely been ended by an unescaped quote.
+ */
+fn truncated_attribute<'a>(error: &Node<'a>, text: &str) -> Option<Node<'a>> {
+    return error
+        .prev_sibling()
+        .filter(|attribute| attribute.kind().ends_with("_attribute"))
+        .filter(|attribute| attribute.end_byte() == error.start_byte())
+        .filter(|_| {
+            error
+                .utf8_text(text.as_bytes())
+                .is_ok_and(|error| error.contains('"'))
+        });
+}
+
+/**
+ * collects the locations at which each variable name is referenced in the given spel.
+ */
+pub(super) fn collect_referenced_names(
+    ast: &SpelAst,
+    names: &mut HashMap<String, Vec<ast::Location>>,
+) {
+    match ast {
+        SpelAst::Comparable(SpelResult::Valid(comparable)) => {
+            collect_names_in_comparable(comparable, names)
+        }
+        SpelAst::Condition(SpelResult::Valid(condition)) => {
+            collect_names_in_condition(condition, names)
+        }
+        SpelAst::Expression(SpelResult::Valid(expression)) => {
+            collect_names_in_expression(expression, names)
+        }
+        SpelAst::Identifier(SpelResult::Valid(identifier)) => {
+            collect_names_in_identifier(identifier, names)
+        }
+        SpelAst::Object(SpelResult::Valid(object)) => collect_names_in_object(object, names),
+        SpelAst::String(SpelResult::Valid(word)) => collect_names_in_word(word, names),
+        SpelAst::Uri(SpelResult::Valid(ast::Uri::Object(interpolation))) => {
+            collect_names_in_object(&interpolation.content, names)
+        }
+        SpelAst::Uri(SpelResult::Valid(ast::Uri::Literal(literal))) => {
+            for fragment in &literal.fragments {
+                collect_names_in_word(&fragment.content, names);
+            }
+            if let Some(extension) = &literal.file_extension {
+                collect_names_in_word(&extension.content, names);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn collect_names_in_identifier(
+    identifier: &ast::Identifier,
+    names: &mut HashMap<String, Vec<ast::Location>>,
+) {
+    match identifier {
+        ast::Identifier::Name(name) => collect_name(name, names),
+        ast::Identifier::FieldAccess {
+            identifier, field, ..
+        } => {
+            collect_names_in_identifier(identifier, names);
+            collect_names_in_word(field, names);
+        }
+    }
+}
+
+fn collect_names_in_object(object: &ast::Object, names: &mut HashMap<String, Vec<ast::Location>>) {
+    match object {
+        ast::Object::Anchor(anchor) => collect_names_in_word(&anchor.name, names),
+        ast::Object::Function(function) => collect_names_in_function(function, names),
+        ast::Object::Name(name) => collect_name(name, names),
+        ast::Object::FieldAccess { object, field, .. } => {
+            collect_names_in_object(object, names);
+            collect_names_in_word(field, names);
+        }
+        ast::Object::MethodAccess {
+            object, function, ..
+        } => {
+            collect_names_in_object(object, names);
+            collect_names_in_function(function, names);
+        }
+        ast::Object::ArrayAccess { object, index, .. } => {
+            collect_names_in_object(object, names);
+            collect_names_in_expression(index, names);
+        }
+        ast::Object::String(_) => {}
+    }
+}
+
+fn collect_names_in_expression(
+    expression: &ast::Expression,
+    names: &mut HashMap<String, Vec<ast::Location>>,
+) {
+    match expression {
+        ast::Expression::Function(function) => collect_names_in_function(function, names),
+        ast::Expression::Object(interpolation) => {
+            collect_names_in_object(&interpolation.content, names)
+        }
+        ast::Expression::SignedExpression { expression, .. }
+        | ast::Expression::BracketedExpression { expression, .. } => {
+            collect_names_in_expression(expression, names)
+        }
+        ast::Expression::BinaryOperation { left, right, .. } => {
+            collect_names_in_expression(left, names);
+            collect_names_in_expression(right, names);
+        }
+        ast::Expression::Ternary {
+            condition,
+            left,
+            right,
+            ..
+        } => {
+            collect_names_in_condition(condition, names);
+            collect_names_in_expression(left, names);
+            collect_names_in_expression(right, names);
+        }
+        _ => {}
+    }
+}
+
+fn collect_names_in_condition(
+    condition: &ast::Condition,
+    names: &mut HashMap<String, Vec<ast::Location>>,
+) {
+    match condition {
+        ast::Condition::Object(interpolation) => {
+            collect_names_in_object(&interpolation.content, names)
+        }
+        ast::Condition::Function(function) => collect_names_in_function(function, names),
+        ast::Condition::BracketedCondition { condition, .. }
+        | ast::Condition::NegatedCondition { condition, .. } => {
+            collect_names_in_condition(condition, names)
+        }
+        ast::Condition::BinaryOperation { left, right, .. } => {
+            collect_names_in_condition(left, names);
+            collect_names_in_condition(right, names);
+        }
+        ast::Condition::Comparisson { left, right, .. } => {
+            collect_names_in_comparable(left, names);
+            collect_names_in_comparable(right, names);
+        }
+        _ => {}
+    }
+}
+
+fn collect_names_in_comparable(
+    comparable: &ast::Comparable,
+    names: &mut HashMap<String, Vec<ast::Location>>,
+) {
+    match comparable {
+        ast::Comparable::Condition(condition) => collect_names_in_condition(condition, names),
+        ast::Comparable::Expression(expression) => collect_names_in_expression(expression, names),
+        ast::Comparable::Function(function) => collect_names_in_function(function, names),
+        ast::Comparable::Object(interpolation) => {
+            collect_names_in_object(&interpolation.content, names)
+        }
+        _ => {}
+    }
+}
+
+fn collect_names_in_function(
+    function: &ast::Function,
+    names: &mut HashMap<String, Vec<ast::Location>>,
+) {
+    for argument in &function.arguments {
+        match &argument.argument {
+            ast::Argument::Anchor(anchor) => collect_names_in_word(&anchor.name, names),
+            ast::Argument::Function(function) => collect_names_in_function(function, names),
+            ast::Argument::Object(interpolation) => {
+                collect_names_in_object(&interpolation.content, names)
+            }
+            _ => {}
+        }
+    }
+}
+
+fn collect_name(word: &ast::Word, names: &mut HashMap<String, Vec<ast::Location>>) {
+    if let Some(ast::WordFragment::String(first)) = word.fragments.first() {
+        if word
+            .fragments
+            .iter()
+            .all(|fragment| matches!(fragment, ast::WordFragment::String(_)))
+        {
+            names
+                .entry(word.to_string())
+                .or_default()
+                .push(first.location.clone());
+        }
+    }
+    collect_names_in_word(word, names);
+}
+
+fn collect_names_in_word(word: &ast::Word, names: &mut HashMap<String, Vec<ast::Location>>) {
+    for fragment in &word.fragments {
+        if let ast::WordFragment::Interpolation(interpolation) = fragment {
+            collect_names_in_object(&interpolation.content, names);
+        }
+    }
+}
+
+/**
+ * interpolations in plain text and html attributes are not parsed, so they are searched for
+ * textually.
+ */
+pub(super) fn find_interpolations_in_text(text: &str, name: &str) -> Vec<usize> {
+    let pattern = format!("${{{}", name);
+    return text
+        .match_indices(&pattern)
+        .map(|(index, _)| index)
+        .filter(|index| {
+            text[index + pattern.len()..]
+                .chars()
+                .next()
+                .is_some_and(|next| !next.is_alphanumeric() && next != '_')
+        })
+        .collect();
+}
+
+/**
+ * collects the opening tag nodes of all tags with a prefix like "sp" in `<sp:print`.
+ */
+fn collect_namespaced_tags<'a>(node: &Node<'a>, text: &str, tags: &mut Vec<(String, Node<'a>)>) {
+    for child in node.children(&mut node.walk()) {
+        if child.kind().ends_with("_tag_open") {
+            let prefix = child
+                .utf8_text(text.as_bytes())
+                .ok()
+                .and_then(|tag| tag.strip_prefix('<'))
+                .and_then(|tag| tag.split_once(':'))
+                .map(|(prefix, _)| prefix.to_string());
+            if let Some(prefix) = prefix {
+                tags.push((prefix, child));
+            }
+        }
+        collect_namespaced_tags(&child, text, tags);
+    }
+}
+
+/**
+ * the tags creating an input field, which `spt:timestamp` may connect to.
+ */
+const FIELD_TAGS: [&str; 12] = [
+    "sp:checkbox",
+    "sp:hidden",
+    "sp:password",
+    "sp:radio",
+    "sp:select",
+    "sp:text",
+    "sp:textarea",
+    "sp:upload",
+    "spt:date",
+    "spt:text",
+    "spt:textarea",
+    "spt:upload",
+];
+
+fn collect_field_names<'a, 'tree>(
+    node: &Node<'tree>,
+    text: &'a str,
+    fields: &mut HashSet<&'a str>,
+    connections: &mut Vec<(&'a str, Node<'tree>)>,
+) {
+    for child in node.children(&mut node.walk()) {
+        if let Ok(tag) = TagDefinition::from_str(child.kind()) {
+            let attribute = match tag.name {
+                "spt:timestamp" => "connect",
+                name if FIELD_TAGS.contains(&name) => "name",
+                _ => "",
+            };
+            for tag_child in child.children(&mut child.walk()) {
+                match parser::attribute_name_and_value_of(tag_child, text) {
+                    Some(("connect", value)) if attribute == "connect" => {
+                        connections.push((value, tag_child));
+                    }
+                    Some(("name", value)) if attribute == "name" => {
+                        fields.insert(value);
+                    }
+                    _ => {}
+                }
+            }
+        }
+        collect_field_names(&child, text, fields, connections);
+    }
+}
+
+fn has_ancestor(node: &Node, kind: &str) -> bool {
+    let mut current = node.parent();
+    while let Some(parent) = current {
+        if parent.kind() == kind {
+            return true;
+        }
+        current = parent.parent();
+    }
+    return false;
+}
+
+/**
+ * script and style bodies are plain text to the browser, but spml tags inside of them are still
+ * parsed. a tag that is only partially written there (e.g. assembled by string concatenation)
+ * breaks the parse and should be pointed out as such.
+ */
+fn is_embedded_spml_error(node: &Node, text: &str) -> bool {
+    if !has_ancestor(node, "script_tag") && !has_ancestor(node, "style_tag") {
+        return false;
+    }
+    return match node.utf8_text(text.as_bytes()) {
+        Ok(content) => content.contains("<sp:") || content.contains("<spt:"),
+        Err(_) => false,
+    };
+}
+
+fn embedding_tag_name(node: &Node) -> &'static str {
+    return match has_ancestor(node, "style_tag") {
+        true => "style",
+        false => "script",
+    };
+}
+
+/**
+ * matches locales like "de" or "en_US".
+ */
+fn is_locale(value: &str) -> bool {
+    let bytes = value.as_bytes();
+    return match bytes.len() {
+        2 => bytes.iter().all(u8::is_ascii_lowercase),
+        5 => {
+            bytes[..2].iter().all(u8::is_ascii_lowercase)
+                && bytes[2] == b'_'
+                && bytes[3..].iter().all(u8::is_ascii_uppercase)
+        }
+        _ => false,
+    };
+}
+
+/**
+ * matches variable names like "_item" or "my-list", optionally accessing fields like "_map.key".
+ */
+fn is_identifier(value: &str) -> bool {
+    return value.split('.').all(|part| {
+        part.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+            && part
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    });
+}
+
+/**
+ * whether the tag has a body (as opposed to being self-closing) that contains nothing but
+ * whitespace.
+ */
+fn has_empty_body(node: &Node, text: &str) -> bool {
+    if !node
+        .child(node.child_count() - 1)
+        .is_some_and(|tag| tag.kind().ends_with("_tag_close"))
+    {
+        return false;
+    }
+    return node
+        .children(&mut node.walk())
+        .all(|child| match child.kind() {
+            ">" => true,
+            "text" => child
+                .utf8_text(text.as_bytes())
+                .is_ok_and(|content| content.trim().is_empty()),
+            kind => {
+                kind.ends_with("_tag_open")
+                    || kind.ends_with("_attribute")
+                    || kind.ends_with("_tag_close")
+            }
+        });
+}
+
+/**
+ * checks a java `SimpleDateFormat` pattern for unclosed quotes and unknown pattern letters.
+ */
+fn date_format_error(value: &str) -> Option<String> {
+    let mut quoted = false;
+    for char in value.chars() {
+        match char {
+            '\'' => quoted = !quoted,
+            _ if quoted => {}
+            'G' | 'y' | 'Y' | 'M' | 'L' | 'w' | 'W' | 'D' | 'd' | 'F' | 'E' | 'u' | 'a' | 'H'
+            | 'k' | 'K' | 'h' | 'm' | 's' | 'S' | 'z' | 'Z' | 'X' => {}
+            char if char.is_ascii_alphabetic() => {
+                return Some(format!("unknown pattern letter '{}'", char))
+            }
+            _ => {}
+        }
+    }
+    return match quoted {
+        true => Some(String::from("unclosed quote")),
+        false => None,
+    };
+}
+
+/**
+ * checks a java `DecimalFormat` pattern for unclosed quotes and missing digits. other characters
+ * are taken literally.
+ */
+fn decimal_format_error(value: &str) -> Option<String> {
+    let mut quoted = false;
+    let mut has_digits = false;
+    for char in value.chars() {
+        match char {
+            '\'' => quoted = !quoted,
+            '0' | '#' if !quoted => has_digits = true,
+            _ => {}
+        }
+    }
+    if quoted {
+        return Some(String::from("unclosed quote"));
+    }
+    if !has_digits {
+        return Some(String::from("missing digit placeholder '0' or '#'"));
+    }
+    return None;
+}
+
 pub(crate) fn diagnostic(params: DocumentDiagnosticParams) -> Result<Vec<Diagnostic>, LsError> {
     let uri = params.text_document.uri;
     let document = match document_store::get(&uri) {
@@ -1038,12 +2400,1300 @@ pub(crate) fn diagnostic(params: DocumentDiagnosticParams) -> Result<Vec<Diagnos
                 };
             }),
     }?;
-    let mut collector = DiagnosticCollector::new(uri, document.text);
-    collector
-        .validate_document(&document.tree.root_node(), &document.spel)
-        .map_err(|err| LsError {
-            message: format!("failed to validate document: {}", err),
-            code: ErrorCode::RequestFailed,
-        })?;
-    return Ok(collector.diagnostics);
+    return diagnose_document(&document, &uri).map_err(|err| LsError {
+        message: format!("failed to validate document: {}", err),
+        code: ErrorCode::RequestFailed,
+    });
+}
+
+/**
+ * collects all diagnostics of an already parsed document, without involving the document store.
+ */
+pub(crate) fn diagnose_document(
+    document: &document_store::Document,
+    uri: &Url,
+) -> Result<Vec<Diagnostic>> {
+    let mut collector = DiagnosticCollector::new(uri.clone(), document.text.clone());
+    collector.validate_document(&document.tree.root_node(), &document.spel)?;
+    return Ok(apply_severity_overrides(
+        collector.diagnostics,
+        &config::get().severity_overrides,
+    ));
+}
+
+/**
+ * diagnoses all spml files below the given directories, without adding them to the document
+ * store. files that cannot be read or parsed are skipped.
+ */
+pub(crate) fn diagnose_all(roots: &[PathBuf]) -> Result<Vec<PublishDiagnosticsParams>> {
+    let mut files = Vec::new();
+    for root in roots {
+        symbol::collect_spml_files(root, &mut files);
+    }
+    let mut results = Vec::new();
+    for file in files {
+        let uri = match Url::from_file_path(&file) {
+            Ok(uri) => uri,
+            Err(_) => {
+                log::info!("failed to create uri from {:?}", file);
+                continue;
+            }
+        };
+        let diagnostics = fs::read_to_string(&file)
+            .map_err(anyhow::Error::from)
+            .and_then(document_store::Document::new)
+            .and_then(|document| diagnose_document(&document, &uri));
+        match diagnostics {
+            Ok(diagnostics) => results.push(PublishDiagnosticsParams {
+                uri,
+                diagnostics,
+                version: None,
+            }),
+            Err(err) => log::info!("failed to diagnose {}: {}", uri, err),
+        }
+    }
+    return Ok(results);
+}
+
+fn apply_severity_overrides(
+    diagnostics: Vec<Diagnostic>,
+    overrides: &[config::SeverityOverride],
+) -> Vec<Diagnostic> {
+    return diagnostics
+        .into_iter()
+        .filter_map(|mut diagnostic| {
+            let severity_override = overrides
+                .iter()
+                .find(|severity_override| severity_override.selector.matches(&diagnostic));
+            if let Some(severity_override) = severity_override {
+                diagnostic.severity = Some(severity_override.severity?);
+            }
+            return Some(diagnostic);
+        })
+        .collect();
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use lsp_types::{
+        Diagnostic, DiagnosticSeverity, DiagnosticTag, Position, Range, TextEdit, Url,
+    };
+
+    use crate::{
+        capabilities::CodeActionImplementation,
+        config::{self, DiagnosticType},
+        document_store::{self, test_document, Document, TestDirectory, TEST_HEADER},
+        modules,
+        spel::{ast::Condition, parser::Parser},
+    };
+
+    use super::{
+        apply_severity_overrides, diagnose_all, diagnose_document, mismatched_literals,
+        DiagnosticCollector,
+    };
+
+    fn collector_for(document: &Document) -> DiagnosticCollector {
+        let _ = modules::init_empty_module_mappings();
+        return DiagnosticCollector::new(
+            Url::parse("file:///some/test/file.spml").unwrap(),
+            document.text.clone(),
+        );
+    }
+
+    fn diagnose_with(
+        document: &Document,
+        configure: impl FnOnce(&mut DiagnosticCollector),
+    ) -> Vec<Diagnostic> {
+        let mut collector = collector_for(document);
+        configure(&mut collector);
+        collector
+            .validate_document(&document.tree.root_node(), &document.spel)
+            .unwrap();
+        return collector.diagnostics;
+    }
+
+    fn diagnose(body: &str) -> Vec<Diagnostic> {
+        return diagnose_with(&test_document(body), |_| {});
+    }
+
+    #[test]
+    fn test_include_without_locator() {
+        let diagnostics = diagnose("<sp:include module=\"test-module\"/>\n");
+        assert!(diagnostics.iter().any(|diagnostic| diagnostic.message
+            == "requires one of these attributes: template, anchor, uri"));
+    }
+
+    #[test]
+    fn test_include_with_uri() {
+        let diagnostics = diagnose("<sp:include uri=\"/some/file.spml\"/>\n");
+        assert!(!diagnostics.iter().any(|diagnostic| diagnostic
+            .message
+            .starts_with("requires one of these attributes")));
+    }
+
+    #[test]
+    fn test_if_with_conflicting_comparators() {
+        let diagnostics = diagnose("<sp:if name=\"_x\" eq=\"1\" neq=\"2\"></sp:if>\n");
+        assert_eq!(
+            diagnostics
+                .iter()
+                .filter(|diagnostic| diagnostic.message
+                    == "can only have one of these attributes: eq, neq")
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_if_with_single_comparator() {
+        let diagnostics = diagnose("<sp:if name=\"_x\" eq=\"1\"></sp:if>\n");
+        assert!(!diagnostics.iter().any(|diagnostic| diagnostic
+            .message
+            .starts_with("can only have one of these attributes")));
+    }
+
+    #[test]
+    fn test_cdata_is_not_reported() {
+        let diagnostics = diagnose(concat!(
+            "<div>\n",
+            "\t<![CDATA[ <sp:print name=\"_x\"> & if (a < b) { } ]]>\n",
+            "</div>\n",
+            "<sp:print name=\"_y\"/>\n",
+        ));
+        assert!(!diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.message.starts_with("unexpected")));
+    }
+
+    #[test]
+    fn test_deprecated_tag_proposes_removal() {
+        let diagnostics = diagnose("<sp:worklist name=\"_list\">\n</sp:worklist>\n");
+        let deprecation = diagnostics
+            .iter()
+            .find(|diagnostic| diagnostic.message == "sp:worklist tag is deprecated")
+            .unwrap();
+        assert_eq!(
+            deprecation.code,
+            Some(CodeActionImplementation::REMOVE_DEPRECATED_TAG_CODE)
+        );
+        assert_eq!(
+            deprecation
+                .data
+                .clone()
+                .and_then(|data| serde_json::from_value::<Vec<TextEdit>>(data).ok()),
+            Some(vec![TextEdit {
+                range: Range {
+                    start: Position {
+                        line: 3,
+                        character: 0,
+                    },
+                    end: Position {
+                        line: 4,
+                        character: 14,
+                    },
+                },
+                new_text: String::new(),
+            }])
+        );
+    }
+
+    #[test]
+    fn test_option_inside_select() {
+        let diagnostics = diagnose(concat!(
+            "<sp:select name=\"_choice\">\n",
+            "\t<sp:option value=\"1\">one</sp:option>\n",
+            "</sp:select>\n",
+        ));
+        assert!(!diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.message.contains("inside of an sp:select")));
+    }
+
+    #[test]
+    fn test_option_outside_of_select() {
+        let diagnostics = diagnose("<sp:option value=\"1\">one</sp:option>\n");
+        assert_eq!(
+            diagnostics
+                .iter()
+                .filter(|diagnostic| diagnostic.message
+                    == "sp:option tag should be inside of an sp:select tag")
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_severity_override_off() {
+        let diagnostics = apply_severity_overrides(
+            diagnose("<sp:worklist name=\"_list\"></sp:worklist>\n"),
+            &[config::SeverityOverride {
+                selector: config::DiagnosticSelector::Tag(DiagnosticTag::DEPRECATED),
+                severity: None,
+            }],
+        );
+        assert!(!diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.message.contains("deprecated")));
+    }
+
+    #[test]
+    fn test_severity_override_remaps_severity() {
+        let diagnostics = apply_severity_overrides(
+            diagnose("<sp:worklist name=\"_list\"></sp:worklist>\n"),
+            &[config::SeverityOverride {
+                selector: config::DiagnosticSelector::Tag(DiagnosticTag::DEPRECATED),
+                severity: Some(DiagnosticSeverity::ERROR),
+            }],
+        );
+        let deprecation = diagnostics
+            .iter()
+            .find(|diagnostic| diagnostic.message == "sp:worklist tag is deprecated")
+            .unwrap();
+        assert_eq!(deprecation.severity, Some(DiagnosticSeverity::ERROR));
+    }
+
+    #[test]
+    fn test_severity_override_by_type() {
+        let diagnostics = apply_severity_overrides(
+            diagnose_with_module_files(
+                "<sp:include uri=\"${_uri}\" module=\"unknown-module\"/>\n",
+                true,
+            ),
+            &[config::SeverityOverride {
+                selector: config::DiagnosticSelector::Type(DiagnosticType::UnknownModule),
+                severity: None,
+            }],
+        );
+        assert!(!diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.message.contains("not listed in module-file")));
+        assert!(diagnostics
+            .iter()
+            .all(|diagnostic| diagnostic.code != Some(DiagnosticType::UnknownModule.code())));
+    }
+
+    #[test]
+    fn test_undeclared_taglib_prefix() {
+        let document = Document::new(format!(
+            "{}{}",
+            concat!(
+                "<%@ page language=\"java\" pageEncoding=\"UTF-8\" contentType=\"text/html; charset=UTF-8\"\n",
+                "%><%@ taglib uri=\"http://www.sitepark.com/taglibs/core\" prefix=\"sp\"\n",
+                "%><%@ taglib tagdir=\"/WEB-INF/tags/spt\" prefix=\"spt\"\n",
+                "%>\n"
+            ),
+            concat!(
+                "<sp:print name=\"_a\"/>\n",
+                "<spt:counter name=\"_b\"/>\n",
+                "<x:foo></x:foo>\n",
+            )
+        ))
+        .unwrap();
+        let diagnostics = diagnose_with(&document, |_| {});
+        assert_eq!(
+            diagnostics
+                .iter()
+                .filter(|diagnostic| diagnostic.message.starts_with("taglib prefix"))
+                .map(|diagnostic| diagnostic.message.as_str())
+                .collect::<Vec<&str>>(),
+            vec!["taglib prefix \"x\" is not declared"]
+        );
+    }
+
+    #[test]
+    fn test_unused_taglib_import() {
+        let document = Document::new(format!(
+            "{}{}",
+            concat!(
+                "<%@ page language=\"java\" pageEncoding=\"UTF-8\" contentType=\"text/html; charset=UTF-8\"\n",
+                "%><%@ taglib uri=\"http://www.sitepark.com/taglibs/core\" prefix=\"sp\"\n",
+                "%><%@ taglib tagdir=\"/WEB-INF/tags/spt\" prefix=\"spt\"\n",
+                "%>\n"
+            ),
+            "<div>\n\t<sp:print name=\"_a\"/>\n</div>\n",
+        ))
+        .unwrap();
+        let diagnostics = diagnose_with(&document, |_| {});
+        let unused: Vec<&Diagnostic> = diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.message.ends_with("is never used"))
+            .collect();
+        assert_eq!(unused.len(), 1);
+        assert_eq!(unused[0].message, "taglib prefix \"spt\" is never used");
+        assert_eq!(
+            unused[0].code,
+            Some(CodeActionImplementation::REMOVE_UNUSED_TAGLIB_CODE)
+        );
+    }
+
+    #[test]
+    fn test_valid_date_format() {
+        let diagnostics = diagnose("<sp:print name=\"_date\" dateformat=\"yyyy-MM-dd\"/>\n");
+        assert!(!diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.message.contains("not a valid date format")));
+    }
+
+    #[test]
+    fn test_date_format_with_unclosed_quote() {
+        let diagnostics = diagnose("<sp:print name=\"_date\" dateformat=\"dd 'um HH:mm\"/>\n");
+        assert!(diagnostics.iter().any(|diagnostic| diagnostic.message
+            == "attribute dateformat is not a valid date format: unclosed quote"));
+    }
+
+    #[test]
+    fn test_invalid_decimal_format() {
+        let diagnostics = diagnose("<sp:print name=\"_number\" decimalformat=\"EUR\"/>\n");
+        assert!(diagnostics.iter().any(|diagnostic| diagnostic.message
+            == "attribute decimalformat is not a valid decimal format: missing digit placeholder '0' or '#'"));
+    }
+
+    #[test]
+    fn test_invalid_form_method() {
+        let diagnostics = diagnose("<sp:form name=\"form\" method=\"delete\"></sp:form>\n");
+        assert!(diagnostics.iter().any(|diagnostic| diagnostic.message
+            == "attribute method should be one of these values: [GET, POST]"));
+    }
+
+    #[test]
+    fn test_uppercase_form_method() {
+        let diagnostics = diagnose("<sp:form name=\"form\" method=\"POST\"></sp:form>\n");
+        assert!(!diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.message.starts_with("attribute method")));
+    }
+
+    #[test]
+    fn test_empty_iterator_body() {
+        let diagnostics =
+            diagnose("<sp:iterator collection=\"${_list}\" item=\"_item\">\n\t\n</sp:iterator>\n");
+        assert!(diagnostics.iter().any(|diagnostic| diagnostic.message
+            == "sp:iterator tag has an empty tag-body"
+            && diagnostic.tags == Some(vec![DiagnosticTag::UNNECESSARY])));
+    }
+
+    #[test]
+    fn test_non_empty_iterator_body() {
+        let diagnostics = diagnose(concat!(
+            "<sp:iterator collection=\"${_list}\" item=\"_item\">\n",
+            "\t<sp:print name=\"_item\"/>\n",
+            "</sp:iterator>\n"
+        ));
+        assert!(!diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.message.ends_with("has an empty tag-body")));
+    }
+
+    #[test]
+    fn test_diagnose_document_without_document_store() {
+        let _ = modules::init_empty_module_mappings();
+        let uri = Url::parse("file:///some/test/in-memory.spml").unwrap();
+        let document = test_document("<sp:print name=\"_a\" text=\"b\"/>\n");
+        let diagnostics = diagnose_document(&document, &uri).unwrap();
+        assert!(document_store::get(&uri).is_none());
+        assert_eq!(
+            diagnostics
+                .iter()
+                .map(|diagnostic| diagnostic.message.as_str())
+                .collect::<Vec<&str>>(),
+            vec!["requires either a tag-body or only one of these attributes: name, text"]
+        );
+    }
+
+    #[test]
+    fn test_mismatched_html_close_tag() {
+        let diagnostics = diagnose("<div></span>\n");
+        assert_eq!(
+            diagnostics
+                .iter()
+                .filter(|diagnostic| diagnostic.message.contains("does not match opening tag"))
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_mismatched_html_close_tag_range() {
+        let diagnostics = diagnose("<div></span>\n");
+        assert_eq!(
+            diagnostics
+                .iter()
+                .find(|diagnostic| diagnostic.message.contains("does not match opening tag"))
+                .map(|diagnostic| diagnostic.range),
+            Some(Range {
+                start: Position::new(3, 7),
+                end: Position::new(3, 11),
+            })
+        );
+    }
+
+    #[test]
+    fn test_unclosed_html_tag() {
+        let diagnostics = diagnose("<div>\n");
+        assert!(!diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.message.contains("does not match opening tag")));
+    }
+
+    #[test]
+    fn test_matching_and_void_html_tags() {
+        let diagnostics = diagnose("<div><br/><img src=\"a.png\"></DIV>\n");
+        assert!(!diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.message.contains("does not match opening tag")));
+    }
+
+    #[test]
+    fn test_known_module_without_uri() {
+        let _ = modules::init_empty_module_mappings();
+        modules::insert_module(
+            "known-module",
+            modules::Module {
+                path: String::from("/some/known/module"),
+            },
+        );
+        let diagnostics = diagnose("<sp:include uri=\"${_uri}\" module=\"known-module\"/>\n");
+        assert!(!diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.message.ends_with("not listed in module-file")));
+    }
+
+    fn diagnose_with_module_files(body: &str, has_module_files: bool) -> Vec<Diagnostic> {
+        return diagnose_with(&test_document(body), |collector| {
+            collector.has_module_files = has_module_files
+        });
+    }
+
+    #[test]
+    fn test_unknown_module_without_uri() {
+        let diagnostics = diagnose_with_module_files(
+            "<sp:include uri=\"${_uri}\" module=\"unknown-module\"/>\n",
+            true,
+        );
+        let unknown: Vec<&Diagnostic> = diagnostics
+            .iter()
+            .filter(|diagnostic| {
+                diagnostic.message == "module \"unknown-module\" not listed in module-file"
+            })
+            .collect();
+        assert_eq!(unknown.len(), 1);
+        assert_eq!(unknown[0].severity, Some(DiagnosticSeverity::HINT));
+    }
+
+    #[test]
+    fn test_unknown_module_without_module_files() {
+        let diagnostics = diagnose_with_module_files(
+            "<sp:include uri=\"${_uri}\" module=\"unknown-module\"/>\n<sp:include uri=\"/a.spml\"/>\n",
+            false,
+        );
+        assert!(!diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.message.ends_with("not listed in module-file")));
+    }
+
+    #[test]
+    fn test_add_missing_taglib_import() {
+        let document = Document::new(String::from(concat!(
+            "<%@ page language=\"java\" pageEncoding=\"UTF-8\" contentType=\"text/html; charset=UTF-8\"\n",
+            "%>\n",
+            "<sp:print name=\"_a\"/>\n",
+        )))
+        .unwrap();
+        let diagnostics = diagnose_with(&document, |_| {});
+        let undeclared = diagnostics
+            .iter()
+            .find(|diagnostic| diagnostic.message == "taglib prefix \"sp\" is not declared")
+            .unwrap();
+        assert_eq!(
+            undeclared.code,
+            Some(CodeActionImplementation::ADD_MISSING_TAGLIB_CODE)
+        );
+        let end = Position {
+            line: 1,
+            character: 2,
+        };
+        assert_eq!(
+            serde_json::from_value::<Vec<TextEdit>>(undeclared.data.clone().unwrap()).unwrap(),
+            vec![TextEdit {
+                range: Range { start: end, end },
+                new_text: String::from(
+                    "<%@ taglib uri=\"http://www.sitepark.com/taglibs/core\" prefix=\"sp\"\n%>"
+                ),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_loop_without_collection_or_list() {
+        let diagnostics =
+            diagnose("<sp:loop item=\"_item\">\n\t<sp:print name=\"_item\"/>\n</sp:loop>\n");
+        assert_eq!(
+            diagnostics
+                .iter()
+                .filter(|diagnostic| diagnostic.message
+                    == "requires one of these attributes: collection, list")
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_loop_with_list() {
+        let diagnostics = diagnose(
+            "<sp:loop list=\"a,b\" item=\"_item\">\n\t<sp:print name=\"_item\"/>\n</sp:loop>\n",
+        );
+        assert!(!diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.message.contains("collection, list")));
+    }
+
+    #[test]
+    fn test_possibly_undefined_variable() {
+        let document = test_document(concat!(
+            "<sp:set name=\"_bar\" value=\"1\"/>\n",
+            "<sp:print text=\"${_foo} ${_bar} ${request.parameter}\"/>\n",
+        ));
+        let diagnostics = diagnose_with(&document, |collector| {
+            collector.warn_undefined_variables = true
+        });
+        assert_eq!(
+            diagnostics,
+            vec![Diagnostic {
+                message: String::from("possibly undefined variable _foo"),
+                severity: Some(DiagnosticSeverity::HINT),
+                range: Range {
+                    start: Position {
+                        line: 4,
+                        character: 18,
+                    },
+                    end: Position {
+                        line: 4,
+                        character: 22,
+                    },
+                },
+                source: Some(String::from("lspml")),
+                code: Some(DiagnosticType::UndefinedVariable.code()),
+                ..Default::default()
+            }]
+        );
+    }
+
+    #[test]
+    fn test_valid_iterator_item() {
+        let diagnostics = diagnose(
+            "<sp:iterator collection=\"${_list}\" item=\"_x\">\n\t<sp:print name=\"_x\"/>\n</sp:iterator>\n",
+        );
+        assert!(!diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.message.contains("valid variable name")));
+    }
+
+    #[test]
+    fn test_invalid_iterator_item() {
+        let diagnostics = diagnose(
+            "<sp:iterator collection=\"${_list}\" item=\"my var\">\n\t<sp:print name=\"_x\"/>\n</sp:iterator>\n",
+        );
+        assert!(diagnostics.iter().any(
+            |diagnostic| diagnostic.message == "attribute item should be a valid variable name"
+        ));
+    }
+
+    #[test]
+    fn test_interpolated_include_uri() {
+        let document = test_document("<sp:include uri=\"/${_folder}/page.spml\"/>\n");
+        let diagnostics = diagnose_with(&document, |collector| {
+            collector.warn_undefined_variables = true
+        });
+        assert_eq!(
+            diagnostics,
+            vec![Diagnostic {
+                message: String::from("possibly undefined variable _folder"),
+                severity: Some(DiagnosticSeverity::HINT),
+                range: Range {
+                    start: Position {
+                        line: 3,
+                        character: 20,
+                    },
+                    end: Position {
+                        line: 3,
+                        character: 27,
+                    },
+                },
+                source: Some(String::from("lspml")),
+                code: Some(DiagnosticType::UndefinedVariable.code()),
+                ..Default::default()
+            }]
+        );
+    }
+
+    #[test]
+    fn test_content_before_header() {
+        let document = Document::new(format!("{}{}", "<div></div>\n", TEST_HEADER)).unwrap();
+        let diagnostics = diagnose_with(&document, |_| {});
+        assert_eq!(
+            diagnostics
+                .into_iter()
+                .filter(|diagnostic| diagnostic.severity == Some(DiagnosticSeverity::ERROR))
+                .collect::<Vec<Diagnostic>>(),
+            vec![Diagnostic {
+                message: String::from("headers have to be placed before any other content"),
+                severity: Some(DiagnosticSeverity::ERROR),
+                range: Range {
+                    start: Position {
+                        line: 0,
+                        character: 0,
+                    },
+                    end: Position {
+                        line: 0,
+                        character: 11,
+                    },
+                },
+                source: Some(String::from("lspml")),
+                code: Some(DiagnosticType::MisplacedHeader.code()),
+                ..Default::default()
+            }]
+        );
+    }
+
+    fn diagnose_html(body: &str, strict_html: bool) -> Vec<Diagnostic> {
+        return diagnose_with(&test_document(body), |collector| {
+            collector.strict_html = strict_html
+        });
+    }
+
+    #[test]
+    fn test_custom_element_in_strict_html() {
+        let diagnostics = diagnose_html(
+            "<my-widget><div><sp:print text=\"x\"/></div></my-widget>\n",
+            true,
+        );
+        assert_eq!(
+            diagnostics,
+            vec![Diagnostic {
+                message: String::from("unknown html tag \"my-widget\""),
+                severity: Some(DiagnosticSeverity::HINT),
+                range: Range {
+                    start: Position {
+                        line: 3,
+                        character: 1,
+                    },
+                    end: Position {
+                        line: 3,
+                        character: 10,
+                    },
+                },
+                source: Some(String::from("lspml")),
+                code: Some(DiagnosticType::UnknownHtmlTag.code()),
+                ..Default::default()
+            }]
+        );
+    }
+
+    #[test]
+    fn test_inline_svg_in_strict_html() {
+        let diagnostics = diagnose_html(
+            "<svg><path d=\"M0 0\"/><g><circle r=\"1\"></circle></g></svg>\n",
+            true,
+        );
+        assert_eq!(diagnostics, vec![]);
+    }
+
+    #[test]
+    fn test_custom_element_in_lax_html() {
+        let diagnostics = diagnose_html(
+            "<my-widget><div><sp:print text=\"x\"/></div></my-widget>\n",
+            false,
+        );
+        assert_eq!(diagnostics, vec![]);
+    }
+
+    #[test]
+    fn test_self_closing_iterator() {
+        let diagnostics = diagnose("<sp:iterator collection=\"${_list}\" item=\"_item\"/>\n");
+        assert!(diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.message == "sp:iterator tag requires a tag-body"));
+    }
+
+    #[test]
+    fn test_iterator_with_body() {
+        let diagnostics = diagnose(concat!(
+            "<sp:iterator collection=\"${_list}\" item=\"_item\">\n",
+            "\t<sp:print name=\"_item\"/>\n",
+            "</sp:iterator>\n",
+        ));
+        assert!(!diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.message.contains("requires a tag-body")));
+    }
+
+    #[test]
+    fn test_conflicting_attributes() {
+        let diagnostics =
+            diagnose("<sp:form name=\"f\" uri=\"/a.spml\" template=\"b\">\n</sp:form>\n");
+        assert!(diagnostics.iter().any(|diagnostic| diagnostic.message
+            == "attribute uri cannot be combined with attribute template"));
+    }
+
+    #[test]
+    fn test_single_of_conflicting_attributes() {
+        let diagnostics = diagnose("<sp:form name=\"f\" template=\"b\">\n</sp:form>\n");
+        assert!(!diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.message.contains("cannot be combined")));
+    }
+
+    #[test]
+    fn test_include_argument_without_name() {
+        let diagnostics =
+            diagnose("<sp:include uri=\"/a.spml\">\n\t<sp:argument value=\"1\"/>\n</sp:include>\n");
+        assert!(diagnostics.iter().any(|diagnostic| diagnostic.message
+            == "missing required attribute name"
+            && diagnostic.range.start.line == 4));
+    }
+
+    #[test]
+    fn test_include_argument_with_name() {
+        let diagnostics = diagnose(
+            "<sp:include uri=\"/a.spml\">\n\t<sp:argument name=\"a\" value=\"1\"/>\n</sp:include>\n",
+        );
+        assert!(!diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.message.starts_with("missing required attribute")));
+    }
+
+    #[test]
+    fn test_page_header_without_content_type() {
+        let document = Document::new(String::from(concat!(
+            "<%@ page language=\"java\" pageEncoding=\"UTF-8\"\n",
+            "%><%@ taglib uri=\"http://www.sitepark.com/taglibs/core\" prefix=\"sp\"\n",
+            "%>\n",
+            "<sp:print name=\"_a\"/>\n",
+        )))
+        .unwrap();
+        let diagnostics = diagnose_with(&document, |collector| {
+            collector.missing_content_type = Some(DiagnosticSeverity::WARNING)
+        });
+        let missing = diagnostics
+            .iter()
+            .find(|diagnostic| diagnostic.message == "page header is missing a contentType")
+            .unwrap();
+        assert_eq!(missing.severity, Some(DiagnosticSeverity::WARNING));
+        assert_eq!(
+            missing.code,
+            Some(CodeActionImplementation::ADD_CONTENT_TYPE_CODE)
+        );
+        let insert = Position {
+            line: 0,
+            character: 8,
+        };
+        assert_eq!(
+            serde_json::from_value::<Vec<TextEdit>>(missing.data.clone().unwrap()).unwrap(),
+            vec![TextEdit {
+                range: Range {
+                    start: insert,
+                    end: insert,
+                },
+                new_text: String::from(" contentType=\"text/html; charset=UTF-8\""),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_page_header_with_content_type() {
+        let mut diagnostics = diagnose("<sp:print name=\"_a\"/>\n");
+        diagnostics.retain(|diagnostic| diagnostic.message.contains("contentType"));
+        assert_eq!(diagnostics, vec![]);
+    }
+
+    #[test]
+    fn test_attribute_and_body_reported_at_attribute() {
+        let diagnostics = diagnose("<sp:checkbox name=\"a\" value=\"b\">b</sp:checkbox>\n");
+        let conflict = diagnostics
+            .iter()
+            .find(|diagnostic| {
+                diagnostic.message == "can only have either a tag-body or the value attribute"
+            })
+            .unwrap();
+        assert_eq!(
+            conflict.range,
+            Range {
+                start: Position {
+                    line: 3,
+                    character: 22,
+                },
+                end: Position {
+                    line: 3,
+                    character: 31,
+                },
+            }
+        );
+    }
+
+    #[test]
+    fn test_request_variable_set_twice() {
+        let diagnostics = diagnose(concat!(
+            "<sp:set name=\"_a\" value=\"1\" scope=\"request\"/>\n",
+            "<sp:set name=\"_a\" value=\"2\" scope=\"request\"/>\n",
+            "<sp:set name=\"_a\" value=\"3\" scope=\"request\" overwrite=\"true\"/>\n",
+            "<sp:set name=\"_b\" value=\"1\"/>\n",
+            "<sp:set name=\"_b\" value=\"2\"/>\n",
+        ));
+        let lines: Vec<u32> = diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.message.contains("is already set"))
+            .map(|diagnostic| diagnostic.range.start.line)
+            .collect();
+        assert_eq!(lines, vec![4]);
+    }
+
+    #[test]
+    fn test_request_variable_inserted_or_defaulted() {
+        let diagnostics = diagnose(concat!(
+            "<sp:set name=\"_a\" value=\"1\" scope=\"request\"/>\n",
+            "<sp:set name=\"_a\" value=\"2\" insert=\"append\" scope=\"request\"/>\n",
+            "<sp:set name=\"_a\" default=\"3\" scope=\"request\">${_b}</sp:set>\n",
+        ));
+        assert!(!diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.message.contains("is already set")));
+    }
+
+    #[test]
+    fn test_request_variable_set_in_sibling_branches() {
+        let diagnostics = diagnose(concat!(
+            "<sp:if name=\"_x\" isNull=\"true\">\n",
+            "\t<sp:set name=\"_a\" value=\"1\" scope=\"request\"/>\n",
+            "</sp:if>\n",
+            "<sp:elseif name=\"_y\" isNull=\"true\">\n",
+            "\t<sp:set name=\"_a\" value=\"2\" scope=\"request\"/>\n",
+            "</sp:elseif>\n",
+            "<sp:else>\n",
+            "\t<sp:set name=\"_a\" value=\"3\" scope=\"request\"/>\n",
+            "</sp:else>\n",
+            "<sp:set name=\"_a\" value=\"4\" scope=\"request\"/>\n",
+        ));
+        let lines: Vec<u32> = diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.message.contains("is already set"))
+            .map(|diagnostic| diagnostic.range.start.line)
+            .collect();
+        assert_eq!(lines, vec![12]);
+    }
+
+    #[test]
+    fn test_unescaped_quote_in_attribute() {
+        let diagnostics = diagnose("<sp:set name=\"_a\" value=\"a\"b\"/>\n");
+        let quote = Range {
+            start: Position {
+                line: 3,
+                character: 26,
+            },
+            end: Position {
+                line: 3,
+                character: 27,
+            },
+        };
+        let unescaped = diagnostics
+            .iter()
+            .find(|diagnostic| diagnostic.message.starts_with("unescaped quote"))
+            .unwrap();
+        assert_eq!(unescaped.range, quote);
+        assert_eq!(
+            unescaped.code,
+            Some(CodeActionImplementation::FIX_SPEL_SYNTAX_CODE)
+        );
+        assert_eq!(
+            serde_json::from_value::<Vec<TextEdit>>(unescaped.data.clone().unwrap()).unwrap(),
+            vec![TextEdit {
+                range: quote,
+                new_text: String::from("\\\""),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_disable_next_line() {
+        let diagnostics = diagnose(concat!(
+            "<%-- lspml-disable --%>\n",
+            "<sp:set value=\"1\"/>\n",
+            "<sp:set value=\"2\"/>\n",
+        ));
+        let lines: Vec<u32> = diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.message == "missing required attribute name")
+            .map(|diagnostic| diagnostic.range.start.line)
+            .collect();
+        assert_eq!(lines, vec![5]);
+    }
+
+    #[test]
+    fn test_disable_line() {
+        let diagnostics = diagnose("<sp:set value=\"1\"/><%-- lspml-disable-line --%>\n");
+        assert!(!diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.message == "missing required attribute name"));
+    }
+
+    #[test]
+    fn test_disable_by_tag() {
+        let diagnostics = diagnose(concat!(
+            "<%-- lspml-disable deprecated --%>\n",
+            "<sp:set value=\"1\"/>\n",
+        ));
+        assert!(diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.message == "missing required attribute name"));
+    }
+
+    #[test]
+    fn test_disable_by_type() {
+        let diagnostics = diagnose(concat!(
+            "<%-- lspml-disable INVALID_ATTRIBUTES --%>\n",
+            "<sp:set value=\"1\"/>\n",
+        ));
+        assert!(!diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.message == "missing required attribute name"));
+    }
+
+    #[test]
+    fn test_disable_unknown_name() {
+        let diagnostics = diagnose(concat!(
+            "<%-- lspml-disable SOMETHING_ELSE --%>\n",
+            "<sp:set value=\"1\"/>\n",
+        ));
+        assert!(diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.message == "missing required attribute name"));
+        let unknown: Vec<&Diagnostic> = diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.code == Some(DiagnosticType::InvalidSuppression.code()))
+            .collect();
+        assert_eq!(unknown.len(), 1);
+        assert_eq!(
+            unknown[0].message,
+            "unknown diagnostic tag or type \"SOMETHING_ELSE\""
+        );
+        assert_eq!(unknown[0].severity, Some(DiagnosticSeverity::HINT));
+    }
+
+    #[test]
+    fn test_for_with_step() {
+        let diagnostics = diagnose(concat!(
+            "<sp:for index=\"_i\" from=\"1\" to=\"10\" step=\"2\">\n",
+            "\t<sp:print name=\"_i\"/>\n",
+            "</sp:for>\n",
+        ));
+        assert!(!diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.message.contains("step")));
+    }
+
+    #[test]
+    fn test_for_with_zero_step() {
+        let diagnostics = diagnose(concat!(
+            "<sp:for index=\"_i\" from=\"1\" to=\"10\" step=\"0\">\n",
+            "\t<sp:print name=\"_i\"/>\n",
+            "</sp:for>\n",
+        ));
+        assert!(diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.message == "attribute step must not be 0"));
+    }
+
+    #[test]
+    fn test_print_with_name_and_text() {
+        let diagnostics = diagnose("<sp:print name=\"_a\" text=\"b\"/>\n");
+        assert!(diagnostics.iter().any(|diagnostic| diagnostic.message
+            == "requires either a tag-body or only one of these attributes: name, text"));
+    }
+
+    #[test]
+    fn test_print_with_text() {
+        let diagnostics = diagnose("<sp:print text=\"b\"/>\n");
+        assert!(!diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.message.starts_with("requires either a tag-body")));
+    }
+
+    fn has_mismatched_literals(condition: &str) -> bool {
+        return match Parser::new(condition).parse_condition_ast().unwrap().root {
+            Condition::Comparisson { left, right, .. } => {
+                mismatched_literals(&left, &right).is_some()
+            }
+            condition => panic!("expected a comparisson, got {:?}", condition),
+        };
+    }
+
+    #[test]
+    fn test_number_compared_to_text() {
+        assert!(has_mismatched_literals("1 == 'abc'"));
+        assert!(has_mismatched_literals("'abc' != 1"));
+    }
+
+    #[test]
+    fn test_number_compared_to_numeric_string() {
+        assert!(!has_mismatched_literals("1 == '1'"));
+        assert!(!has_mismatched_literals("1 == 2"));
+        assert!(!has_mismatched_literals("${_a} == 'abc'"));
+    }
+
+    #[test]
+    fn test_mismatched_comparisson_hint() {
+        let diagnostics = diagnose("<sp:if condition=\"1 == 'abc'\">\n\tx\n</sp:if>\n");
+        assert_eq!(
+            diagnostics
+                .iter()
+                .filter(|diagnostic| diagnostic.message
+                    == "comparing a number with a non-numeric string")
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_scaleimage_background_without_padding() {
+        let diagnostics =
+            diagnose("<sp:scaleimage name=\"_img\" width=\"100\" background=\"#fff\"/>\n");
+        let useless = diagnostics
+            .iter()
+            .find(|diagnostic| {
+                diagnostic
+                    .message
+                    .starts_with("attribute background is useless")
+            })
+            .unwrap();
+        assert_eq!(
+            useless.range,
+            Range {
+                start: Position {
+                    line: 3,
+                    character: 39,
+                },
+                end: Position {
+                    line: 3,
+                    character: 56,
+                },
+            }
+        );
+    }
+
+    #[test]
+    fn test_scaleimage_background_with_padding() {
+        let diagnostics = diagnose(
+            "<sp:scaleimage name=\"_img\" width=\"100\" padding=\"on\" background=\"#fff\"/>\n",
+        );
+        assert!(!diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.message.contains("useless")));
+    }
+
+    #[test]
+    fn test_invalid_regex() {
+        let diagnostics = diagnose("<sp:if name=\"_a\" match=\"[a-z\">a</sp:if>\n");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].message,
+            "invalid regex: unclosed character class. try adding \"]\""
+        );
+        assert_eq!(
+            diagnostics[0].code,
+            Some(CodeActionImplementation::FIX_SPEL_SYNTAX_CODE)
+        );
+        assert_eq!(
+            diagnostics[0].range,
+            Range {
+                start: Position::new(3, 24),
+                end: Position::new(3, 28),
+            }
+        );
+    }
+
+    #[test]
+    fn test_timestamp_connected_to_field() {
+        let diagnostics = diagnose(concat!(
+            "<spt:timestamp connect=\"_date\"/>\n",
+            "<sp:hidden name=\"_date\"/>\n",
+        ));
+        assert!(!diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.message.starts_with("no input field")));
+    }
+
+    #[test]
+    fn test_timestamp_connected_to_unknown_field() {
+        let diagnostics: Vec<Diagnostic> = diagnose(concat!(
+            "<spt:timestamp connect=\"_other\"/>\n",
+            "<sp:hidden name=\"_date\"/>\n",
+        ))
+        .into_iter()
+        .filter(|diagnostic| diagnostic.message.starts_with("no input field"))
+        .collect();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].message,
+            "no input field named \"_other\" found in this document"
+        );
+        assert_eq!(diagnostics[0].severity, Some(DiagnosticSeverity::HINT));
+        assert_eq!(
+            diagnostics[0].range,
+            Range {
+                start: Position::new(3, 15),
+                end: Position::new(3, 31),
+            }
+        );
+    }
+
+    #[test]
+    fn test_diagnose_all_matches_single_files() {
+        let _ = modules::init_empty_module_mappings();
+        let directory = TestDirectory::new("diagnose-all");
+        let root = &directory.path;
+        fs::create_dir_all(root.join("nested")).unwrap();
+        let files = [
+            (root.join("first.spml"), "<sp:print/>\n"),
+            (
+                root.join("nested").join("second.spml"),
+                "<sp:set name=\"_a\" value=\"1\" expression=\"2\"/>\n",
+            ),
+        ];
+        for (file, body) in &files {
+            fs::write(file, format!("{}{}", TEST_HEADER, body)).unwrap();
+        }
+        let mut results = diagnose_all(std::slice::from_ref(root)).unwrap();
+        results.sort_by(|a, b| a.uri.as_str().cmp(b.uri.as_str()));
+        assert_eq!(results.len(), 2);
+        for ((file, body), result) in files.iter().zip(results) {
+            let uri = Url::from_file_path(file).unwrap();
+            let document = test_document(body);
+            assert_eq!(result.uri, uri);
+            assert!(!result.diagnostics.is_empty());
+            assert_eq!(
+                result.diagnostics,
+                diagnose_document(&document, &uri).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_set_insert_with_value() {
+        let diagnostics = diagnose(concat!(
+            "<sp:set name=\"_a\" value=\"a\"/>\n",
+            "<sp:set name=\"_a\" insert=\"append\" value=\"b\"/>\n",
+        ));
+        assert_eq!(diagnostics, vec![]);
+    }
+
+    #[test]
+    fn test_set_insert_with_condition() {
+        let diagnostics = diagnose("<sp:set name=\"_a\" insert=\"append\" condition=\"true\"/>\n");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].message,
+            "attribute insert is useless without either a tag-body or one of these attributes: value, expression, object"
+        );
+        assert_eq!(diagnostics[0].range.start, Position::new(3, 18));
+    }
+
+    #[test]
+    fn test_unexpected_content_in_condition() {
+        let diagnostics = diagnose("<sp:condition>\n\t<sp:print name=\"_a\"/>\n</sp:condition>\n");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].message, "unexpected print tag");
+        assert_eq!(diagnostics[0].range.start, Position::new(4, 1));
+    }
+
+    #[test]
+    fn test_html_and_text_in_condition() {
+        let diagnostics = diagnose(concat!(
+            "<sp:condition>\n",
+            "\t<!-- comment -->\n",
+            "\t<sp:if name=\"_a\" isNull=\"true\">a</sp:if>\n",
+            "\ttext\n",
+            "\t<div></div>\n",
+            "</sp:condition>\n",
+        ));
+        let messages: Vec<&str> = diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.message.as_str())
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                "unexpected content in sp:condition tag",
+                "unexpected content in sp:condition tag",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_incomplete_spml_tag_in_script() {
+        let diagnostics = diagnose(concat!(
+            "<script>\n",
+            "\tvar a = '<sp:print name=\"_a\"';\n",
+            "</script>\n",
+        ));
+        assert!(diagnostics.iter().any(|diagnostic| diagnostic.message
+            == "incomplete spml tag inside script body. Escape \"<\" or move the tag out"));
+        assert!(!diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.message.starts_with("unexpected")));
+    }
+
+    #[test]
+    fn test_spml_tag_in_script() {
+        let diagnostics = diagnose(concat!(
+            "<script>\n",
+            "\tvar a = '<sp:print text=\"a\"/>';\n",
+            "</script>\n",
+        ));
+        assert!(diagnostics.is_empty());
+    }
+
+    #[test]
+    fn test_valid_locale() {
+        let diagnostics = diagnose("<sp:print name=\"value\" locale=\"de_DE\"/>\n");
+        assert!(!diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.message.contains("should be a locale")));
+    }
+
+    #[test]
+    fn test_invalid_locale() {
+        let diagnostics = diagnose("<sp:print name=\"value\" locale=\"german\"/>\n");
+        assert!(diagnostics.iter().any(|diagnostic| diagnostic.message
+            == "attribute locale should be a locale like \"de\" or \"en_US\""));
+    }
+
+    #[test]
+    fn test_interpolated_locale() {
+        let diagnostics = diagnose("<sp:print name=\"value\" locale=\"${locale}\"/>\n");
+        assert!(!diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.message.contains("should be a locale")));
+    }
+
+    #[test]
+    fn test_unused_variables() {
+        let document = test_document(concat!(
+            "<sp:set name=\"_used\" value=\"1\"/>\n",
+            "<sp:set name=\"_unused\" value=\"2\"/>\n",
+            "<sp:print name=\"_used\"/>\n",
+        ));
+        let mut collector = collector_for(&document);
+        collector
+            .validate_unused_variables(&document.tree.root_node(), &document.spel)
+            .unwrap();
+        assert_eq!(
+            collector
+                .diagnostics
+                .iter()
+                .map(|diagnostic| diagnostic.message.as_str())
+                .collect::<Vec<&str>>(),
+            vec!["variable _unused is never used"]
+        );
+    }
 }
@@ -0,0 +1,227 @@
+use std::collections::HashMap;
+
+use lsp_server::ErrorCode;
+use lsp_types::{ExecuteCommandParams, Location, Url, WorkspaceEdit};
+use serde_json::Value;
+
+use crate::{
+    document_store::{self, Document},
+    modules,
+};
+
+use super::{action, diagnostic, lens, LsError};
+
+pub(crate) const OPEN_MODULE_FILE: &str = "lspml.openModuleFile";
+pub(crate) const FIX_SPEL_SYNTAX: &str = "lspml.fixSpelSyntax";
+pub(crate) const FIND_REFERENCES: &str = "lspml.findReferences";
+
+/**
+ * the outcome of a command. an `edit` has to be applied by the client via `workspace/applyEdit`.
+ */
+pub(crate) struct Execution {
+    pub(crate) result: Option<Value>,
+    pub(crate) edit: Option<WorkspaceEdit>,
+}
+
+/**
+ * the commands that can be executed via `workspace/executeCommand`.
+ */
+pub(crate) fn commands() -> Vec<String> {
+    return vec![
+        OPEN_MODULE_FILE.to_string(),
+        FIX_SPEL_SYNTAX.to_string(),
+        FIND_REFERENCES.to_string(),
+    ];
+}
+
+pub(crate) fn execute(params: ExecuteCommandParams) -> Result<Execution, LsError> {
+    return match params.command.as_str() {
+        OPEN_MODULE_FILE => {
+            open_module_file(&params.arguments, modules::module_files()).map(|result| Execution {
+                result: Some(result),
+                edit: None,
+            })
+        }
+        FIX_SPEL_SYNTAX => fix_spel_syntax(&params.arguments).map(|edit| Execution {
+            result: None,
+            edit: Some(edit),
+        }),
+        FIND_REFERENCES => find_references(&params.arguments).map(|result| Execution {
+            result: Some(result),
+            edit: None,
+        }),
+        command => Err(LsError {
+            message: format!("unknown command \"{}\"", command),
+            code: ErrorCode::InvalidRequest,
+        }),
+    };
+}
+
+/**
+ * without arguments the paths of the given module files, otherwise the path of the module with
+ * the name given as first argument.
+ */
+fn open_module_file(arguments: &[Value], module_files: &[String]) -> Result<Value, LsError> {
+    return match arguments.first() {
+        None => Ok(Value::from(module_files.to_vec())),
+        Some(Value::String(name)) => match modules::find_module_by_name(name) {
+            Some(module) => Ok(Value::from(module.path)),
+            None => Err(LsError {
+                message: format!("module \"{}\" is not listed in the module-file", name),
+                code: ErrorCode::InvalidParams,
+            }),
+        },
+        Some(argument) => Err(LsError {
+            message: format!("expected a module name, got {}", argument),
+            code: ErrorCode::InvalidParams,
+        }),
+    };
+}
+
+/**
+ * applies the proposed fixes of all spel syntax errors in the document with the uri given as
+ * first argument at once.
+ */
+fn fix_spel_syntax(arguments: &[Value]) -> Result<WorkspaceEdit, LsError> {
+    let (uri, document) = document_argument(arguments)?;
+    return spel_syntax_fixes(&document, &uri);
+}
+
+/**
+ * the locations of all references to the variable named by the second argument within the
+ * document with the uri given as first argument.
+ */
+fn find_references(arguments: &[Value]) -> Result<Value, LsError> {
+    let (uri, document) = document_argument(arguments)?;
+    let name = match arguments.get(1) {
+        Some(Value::String(name)) => Ok(name),
+        Some(argument) => Err(LsError {
+            message: format!("expected a variable name, got {}", argument),
+            code: ErrorCode::InvalidParams,
+        }),
+        None => Err(LsError {
+            message: "expected a variable name".to_string(),
+            code: ErrorCode::InvalidParams,
+        }),
+    }?;
+    let locations: Vec<Location> = lens::find_references(&document, name)
+        .into_iter()
+        .map(|range| Location::new(uri.clone(), range))
+        .collect();
+    return serde_json::to_value(locations).map_err(|err| LsError {
+        message: format!("failed to serialize references: {}", err),
+        code: ErrorCode::InternalError,
+    });
+}
+
+/**
+ * the document with the uri given as first argument.
+ */
+fn document_argument(arguments: &[Value]) -> Result<(Url, Document), LsError> {
+    let uri = match arguments.first() {
+        Some(Value::String(uri)) => Url::parse(uri).map_err(|err| LsError {
+            message: format!("invalid document uri \"{}\": {}", uri, err),
+            code: ErrorCode::InvalidParams,
+        }),
+        Some(argument) => Err(LsError {
+            message: format!("expected a document uri, got {}", argument),
+            code: ErrorCode::InvalidParams,
+        }),
+        None => Err(LsError {
+            message: "expected a document uri".to_string(),
+            code: ErrorCode::InvalidParams,
+        }),
+    }?;
+    let document = match document_store::get(&uri) {
+        Some(document) => Ok(document),
+        None => document_store::Document::from_uri(&uri)
+            .map(|document| document_store::put(&uri, document))
+            .map_err(|err| {
+                log::error!("failed to read {}: {}", uri, err);
+                return LsError {
+                    message: format!("cannot read file {}", uri),
+                    code: ErrorCode::RequestFailed,
+                };
+            }),
+    }?;
+    return Ok((uri, document));
+}
+
+fn spel_syntax_fixes(document: &Document, uri: &Url) -> Result<WorkspaceEdit, LsError> {
+    let diagnostics = diagnostic::diagnose_document(document, uri).map_err(|err| LsError {
+        message: format!("failed to validate document: {}", err),
+        code: ErrorCode::RequestFailed,
+    })?;
+    return Ok(WorkspaceEdit {
+        changes: Some(HashMap::from([(
+            uri.clone(),
+            action::collect_spel_syntax_edits(&diagnostics),
+        )])),
+        ..WorkspaceEdit::default()
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use lsp_types::{Position, Range, TextEdit, Url};
+    use serde_json::Value;
+
+    use crate::{
+        document_store::test_document,
+        modules::{self, Module},
+    };
+
+    use super::{open_module_file, spel_syntax_fixes};
+
+    #[test]
+    fn test_open_module_file_of_named_module() {
+        modules::insert_module(
+            "lspml-test-execute-module",
+            Module {
+                path: "/some/modules/execute".to_string(),
+            },
+        );
+        assert_eq!(
+            open_module_file(&[Value::from("lspml-test-execute-module")], &[]).ok(),
+            Some(Value::from("/some/modules/execute"))
+        );
+        assert!(open_module_file(&[Value::from("lspml-test-unknown-module")], &[]).is_err());
+    }
+
+    #[test]
+    fn test_open_module_file_without_arguments() {
+        let module_files = vec![
+            String::from("/some/config/modules.json"),
+            String::from("/some/other/modules.json"),
+        ];
+        assert_eq!(
+            open_module_file(&[], &module_files).ok(),
+            Some(Value::from(vec![
+                "/some/config/modules.json",
+                "/some/other/modules.json"
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_fix_spel_syntax() {
+        let _ = modules::init_empty_module_mappings();
+        let uri = Url::parse("file:///some/test/file.spml").unwrap();
+        let document = test_document(concat!(
+            "<sp:set name=\"_a\" expression=\"(1 + 2\"/>\n",
+            "<sp:set name=\"_b\" expression=\"(3 + 4\"/>\n",
+        ));
+        let edits = spel_syntax_fixes(&document, &uri)
+            .unwrap()
+            .changes
+            .and_then(|mut changes| changes.remove(&uri));
+        let insert = |line, character| TextEdit {
+            range: Range {
+                start: Position { line, character },
+                end: Position { line, character },
+            },
+            new_text: ")".to_string(),
+        };
+        assert_eq!(edits, Some(vec![insert(3, 36), insert(4, 36)]));
+    }
+}
@@ -0,0 +1,163 @@
+use lsp_server::ErrorCode;
+use lsp_types::{FoldingRange, FoldingRangeKind, FoldingRangeParams};
+use tree_sitter::Node;
+
+use crate::document_store::{self, Document};
+
+use super::LsError;
+
+/**
+ * folds multi-line comments and tags. ranges end on the line before the closing part, so it
+ * stays visible.
+ */
+pub(crate) fn folding(params: FoldingRangeParams) -> Result<Vec<FoldingRange>, LsError> {
+    let uri = params.text_document.uri;
+    let document = match document_store::get(&uri) {
+        Some(document) => Ok(document),
+        None => document_store::Document::from_uri(&uri)
+            .map(|document| document_store::put(&uri, document))
+            .map_err(|err| {
+                log::error!("failed to read {}: {}", uri, err);
+                return LsError {
+                    message: format!("cannot read file {}", uri),
+                    code: ErrorCode::RequestFailed,
+                };
+            }),
+    }?;
+    return Ok(folding_ranges(&document));
+}
+
+fn folding_ranges(document: &Document) -> Vec<FoldingRange> {
+    let mut ranges = Vec::new();
+    collect_folding_ranges(&document.tree.root_node(), &mut ranges);
+    return ranges;
+}
+
+fn collect_folding_ranges(node: &Node, ranges: &mut Vec<FoldingRange>) {
+    for child in node.children(&mut node.walk()) {
+        let start_line = child.start_position().row as u32;
+        let end_line = child.end_position().row as u32;
+        match child.kind() {
+            "comment" | "xml_comment" if end_line > start_line + 1 => ranges.push(FoldingRange {
+                start_line,
+                end_line: end_line - 1,
+                kind: Some(FoldingRangeKind::Comment),
+                ..Default::default()
+            }),
+            "comment" | "xml_comment" => {}
+            kind if kind.ends_with("_tag") && end_line > start_line + 1 => {
+                ranges.push(FoldingRange {
+                    start_line,
+                    end_line: end_line - 1,
+                    ..Default::default()
+                });
+                collect_folding_ranges(&child, ranges);
+            }
+            _ => collect_folding_ranges(&child, ranges),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lsp_types::{FoldingRange, FoldingRangeKind, Url};
+
+    use crate::{
+        command::diagnostic,
+        document_store::{test_document, Document},
+        modules,
+    };
+
+    use super::folding_ranges;
+
+    #[test]
+    fn test_fold_multiline_comments() {
+        let _ = modules::init_empty_module_mappings();
+        let document = test_document(concat!(
+            "<%-- a comment\n",
+            "\tspanning multiple lines\n",
+            "--%>\n",
+            "<!-- a single line comment -->\n",
+            "<!--\n",
+            "\t<sp:print name=\"_commented\"/>\n",
+            "-->\n",
+        ));
+        assert_eq!(
+            folding_ranges(&document),
+            vec![
+                FoldingRange {
+                    start_line: 3,
+                    end_line: 4,
+                    kind: Some(FoldingRangeKind::Comment),
+                    ..Default::default()
+                },
+                FoldingRange {
+                    start_line: 7,
+                    end_line: 8,
+                    kind: Some(FoldingRangeKind::Comment),
+                    ..Default::default()
+                },
+            ]
+        );
+        let diagnostics = diagnostic::diagnose_document(
+            &document,
+            &Url::parse("file:///some/test/file.spml").unwrap(),
+        )
+        .unwrap();
+        assert_eq!(diagnostics, vec![]);
+    }
+
+    #[test]
+    fn test_fold_multiline_tags() {
+        let document = test_document(concat!(
+            "<div>\n",
+            "\t<sp:iterator collection=\"${_list}\" item=\"_item\">\n",
+            "\t\t<sp:print name=\"_item\"/>\n",
+            "\t</sp:iterator>\n",
+            "</div>\n",
+        ));
+        assert_eq!(
+            folding_ranges(&document),
+            vec![
+                FoldingRange {
+                    start_line: 3,
+                    end_line: 6,
+                    ..Default::default()
+                },
+                FoldingRange {
+                    start_line: 4,
+                    end_line: 5,
+                    ..Default::default()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fold_comment_between_headers() {
+        let _ = modules::init_empty_module_mappings();
+        let document = Document::new(String::from(concat!(
+            "<%@ page language=\"java\" pageEncoding=\"UTF-8\" contentType=\"text/html; charset=UTF-8\"\n",
+            "%><%-- the core taglib\n",
+            "--%><%@ taglib uri=\"http://www.sitepark.com/taglibs/core\" prefix=\"sp\"\n",
+            "%>\n",
+            "<sp:print name=\"_a\"/>\n",
+        )))
+        .unwrap();
+        assert_eq!(
+            folding_ranges(&document),
+            vec![FoldingRange {
+                start_line: 1,
+                end_line: 2,
+                kind: Some(FoldingRangeKind::Comment),
+                ..Default::default()
+            }]
+        );
+        let diagnostics = diagnostic::diagnose_document(
+            &document,
+            &Url::parse("file:///some/test/file.spml").unwrap(),
+        )
+        .unwrap();
+        assert_eq!(diagnostics, vec![]);
+    }
+}
@@ -0,0 +1,109 @@
+use lsp_server::ErrorCode;
+use lsp_types::{DocumentOnTypeFormattingParams, Position, Range, TextEdit};
+use tree_sitter::Point;
+
+use crate::document_store::{self, Document};
+
+use super::LsError;
+
+/**
+ * inserts the closing tag of an spml tag, whose opening tag has just been completed by typing
+ * `>`, right after the cursor.
+ */
+pub(crate) fn on_type_formatting(
+    params: DocumentOnTypeFormattingParams,
+) -> Result<Vec<TextEdit>, LsError> {
+    let uri = params.text_document_position.text_document.uri;
+    let document = match document_store::get(&uri) {
+        Some(document) => Ok(document),
+        None => document_store::Document::from_uri(&uri)
+            .map(|document| document_store::put(&uri, document))
+            .map_err(|err| {
+                log::error!("failed to read {}: {}", uri, err);
+                return LsError {
+                    message: format!("cannot read file {}", uri),
+                    code: ErrorCode::RequestFailed,
+                };
+            }),
+    }?;
+    if params.ch != ">" {
+        return Ok(Vec::new());
+    }
+    return Ok(close_tag(&document, params.text_document_position.position)
+        .into_iter()
+        .collect());
+}
+
+fn close_tag(document: &Document, position: Position) -> Option<TextEdit> {
+    let bracket_position = Point {
+        row: position.line as usize,
+        column: (position.character as usize).checked_sub(1)?,
+    };
+    let bracket = document
+        .tree
+        .root_node()
+        .descendant_for_point_range(bracket_position, bracket_position)?;
+    if bracket.kind() != ">" || bracket.is_missing() {
+        return None;
+    }
+    let tag = bracket.parent()?;
+    let open = tag.child(0).filter(|open| {
+        let kind = open.kind();
+        return kind.ends_with("_tag_open") && kind != "html_tag_open";
+    })?;
+    if tag
+        .child(tag.child_count() - 1)
+        .is_some_and(|close| close.kind().ends_with("_tag_close") && !close.is_missing())
+    {
+        return None;
+    }
+    let name = open
+        .utf8_text(document.text.as_bytes())
+        .ok()?
+        .strip_prefix("<")?;
+    return Some(TextEdit {
+        range: Range {
+            start: position,
+            end: position,
+        },
+        new_text: format!("</{}>", name),
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use lsp_types::{Position, Range, TextEdit};
+
+    use crate::document_store::test_document;
+
+    use super::close_tag;
+
+    #[test]
+    fn test_close_iterator_tag() {
+        let document = test_document("<sp:iterator collection=\"${_list}\" item=\"_item\">\n");
+        let position = Position {
+            line: 3,
+            character: 48,
+        };
+        assert_eq!(
+            close_tag(&document, position),
+            Some(TextEdit {
+                range: Range {
+                    start: position,
+                    end: position,
+                },
+                new_text: "</sp:iterator>".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_do_not_close_self_closing_tag() {
+        let document = test_document("<sp:print name=\"_a\"/>\n");
+        let position = Position {
+            line: 3,
+            character: 21,
+        };
+        assert_eq!(close_tag(&document, position), None);
+    }
+}
@@ -0,0 +1,231 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Result;
+use lsp_server::ErrorCode;
+use lsp_types::{
+    CallHierarchyIncomingCall, CallHierarchyIncomingCallsParams, CallHierarchyItem,
+    CallHierarchyOutgoingCall, CallHierarchyOutgoingCallsParams, CallHierarchyPrepareParams,
+    Position, Range, SymbolKind, Url,
+};
+use tree_sitter::Node;
+
+use crate::{
+    document_store::{self, Document},
+    modules, parser,
+};
+
+use super::{symbol, LsError};
+
+/**
+ * spml files are the items of the call hierarchy and `<sp:include>` tags the calls between them.
+ */
+pub(crate) fn prepare(
+    params: CallHierarchyPrepareParams,
+) -> Result<Vec<CallHierarchyItem>, LsError> {
+    let uri = params.text_document_position_params.text_document.uri;
+    return uri
+        .to_file_path()
+        .map_err(|_| LsError {
+            message: format!("cannot read file {}", uri),
+            code: ErrorCode::RequestFailed,
+        })
+        .map(|file| vec![file_item(&file, uri)]);
+}
+
+pub(crate) fn incoming(
+    params: CallHierarchyIncomingCallsParams,
+) -> Result<Vec<CallHierarchyIncomingCall>, LsError> {
+    let roots: Vec<PathBuf> = modules::all_modules()
+        .into_iter()
+        .map(|(_, module)| PathBuf::from(module.path))
+        .collect();
+    return params
+        .item
+        .uri
+        .to_file_path()
+        .map_err(|_| anyhow::anyhow!("failed to read file path from uri {}", params.item.uri))
+        .and_then(|file| incoming_calls(&file, &roots))
+        .map_err(|err| LsError {
+            message: format!("failed to search incoming calls: {}", err),
+            code: ErrorCode::RequestFailed,
+        });
+}
+
+pub(crate) fn outgoing(
+    params: CallHierarchyOutgoingCallsParams,
+) -> Result<Vec<CallHierarchyOutgoingCall>, LsError> {
+    return params
+        .item
+        .uri
+        .to_file_path()
+        .map_err(|_| anyhow::anyhow!("failed to read file path from uri {}", params.item.uri))
+        .and_then(|file| outgoing_calls(&file))
+        .map_err(|err| LsError {
+            message: format!("failed to search outgoing calls: {}", err),
+            code: ErrorCode::RequestFailed,
+        });
+}
+
+fn incoming_calls(file: &Path, roots: &[PathBuf]) -> Result<Vec<CallHierarchyIncomingCall>> {
+    let mut files = Vec::new();
+    for root in roots {
+        symbol::collect_spml_files(root, &mut files);
+    }
+    let mut calls = Vec::new();
+    for caller in files {
+        let includes = match includes_of(&caller) {
+            Ok(includes) => includes,
+            Err(err) => {
+                log::info!("failed to collect includes of {:?}: {}", caller, err);
+                continue;
+            }
+        };
+        let from_ranges: Vec<Range> = includes
+            .into_iter()
+            .filter(|(included, _)| included == file)
+            .map(|(_, range)| range)
+            .collect();
+        if !from_ranges.is_empty() {
+            let uri = Url::from_file_path(&caller)
+                .map_err(|_| anyhow::anyhow!("failed to create uri from {:?}", caller))?;
+            calls.push(CallHierarchyIncomingCall {
+                from: file_item(&caller, uri),
+                from_ranges,
+            });
+        }
+    }
+    return Ok(calls);
+}
+
+fn outgoing_calls(file: &Path) -> Result<Vec<CallHierarchyOutgoingCall>> {
+    let mut calls: Vec<(PathBuf, Vec<Range>)> = Vec::new();
+    for (included, range) in includes_of(file)? {
+        match calls.iter_mut().find(|(path, _)| *path == included) {
+            Some((_, ranges)) => ranges.push(range),
+            None => calls.push((included, vec![range])),
+        }
+    }
+    let mut result = Vec::new();
+    for (included, from_ranges) in calls {
+        let uri = Url::from_file_path(&included)
+            .map_err(|_| anyhow::anyhow!("failed to create uri from {:?}", included))?;
+        result.push(CallHierarchyOutgoingCall {
+            to: file_item(&included, uri),
+            from_ranges,
+        });
+    }
+    return Ok(result);
+}
+
+/**
+ * all existing files included by the given file, together with the ranges of the including
+ * `<sp:include>` tags. includes with interpolated uris cannot be resolved and are skipped.
+ */
+fn includes_of(file: &Path) -> Result<Vec<(PathBuf, Range)>> {
+    let uri = Url::from_file_path(file)
+        .map_err(|_| anyhow::anyhow!("failed to create uri from {:?}", file))?;
+    let document = match document_store::get(&uri) {
+        Some(document) => document,
+        None => Document::new(fs::read_to_string(file)?)?,
+    };
+    let mut tags = Vec::new();
+    collect_include_tags(&document.tree.root_node(), &mut tags);
+    let mut includes = Vec::new();
+    for tag in tags {
+        let mut attributes = HashMap::new();
+        for child in tag.children(&mut tag.walk()) {
+            if let Some((name, value)) = parser::attribute_name_and_value_of(child, &document.text)
+            {
+                attributes.insert(name, value);
+            }
+        }
+        let included = match attributes.get("uri") {
+            Some(uri) if !uri.contains("${") => {
+                modules::resolve_module(attributes.get("module").copied(), file)
+                    .map(|module| PathBuf::from(format!("{}{}", module.path, uri)))
+            }
+            _ => None,
+        };
+        if let Some(included) = included.filter(|included| included.exists()) {
+            includes.push((included, node_range(&tag)));
+        }
+    }
+    return Ok(includes);
+}
+
+fn collect_include_tags<'a>(node: &Node<'a>, tags: &mut Vec<Node<'a>>) {
+    for child in node.children(&mut node.walk()) {
+        if child.kind() == "include_tag" {
+            tags.push(child);
+        }
+        collect_include_tags(&child, tags);
+    }
+}
+
+fn file_item(file: &Path, uri: Url) -> CallHierarchyItem {
+    return CallHierarchyItem {
+        name: file
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| uri.to_string()),
+        kind: SymbolKind::FILE,
+        tags: None,
+        detail: Some(file.to_string_lossy().to_string()),
+        uri,
+        range: Range::default(),
+        selection_range: Range::default(),
+        data: None,
+    };
+}
+
+fn node_range(node: &Node) -> Range {
+    return Range {
+        start: Position {
+            line: node.start_position().row as u32,
+            character: node.start_position().column as u32,
+        },
+        end: Position {
+            line: node.end_position().row as u32,
+            character: node.end_position().column as u32,
+        },
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::document_store::{TestDirectory, TEST_HEADER};
+
+    use super::{incoming_calls, outgoing_calls};
+
+    #[test]
+    fn test_calls_between_including_files() {
+        let mut directory = TestDirectory::new("call-hierarchy");
+        directory.register_module();
+        let root = &directory.path;
+        let caller = root.join("caller.spml");
+        let callee = root.join("callee.spml");
+        fs::write(
+            &caller,
+            format!("{}{}", TEST_HEADER, "<sp:include uri=\"/callee.spml\"/>\n"),
+        )
+        .unwrap();
+        fs::write(
+            &callee,
+            format!("{}{}", TEST_HEADER, "<sp:print text=\"hi\"/>\n"),
+        )
+        .unwrap();
+        let outgoing = outgoing_calls(&caller).unwrap();
+        let incoming = incoming_calls(&callee, std::slice::from_ref(root)).unwrap();
+        assert_eq!(outgoing.len(), 1);
+        assert_eq!(outgoing[0].to.name, "callee.spml");
+        assert_eq!(outgoing[0].from_ranges[0].start.line, 3);
+        assert_eq!(incoming.len(), 1);
+        assert_eq!(incoming[0].from.name, "caller.spml");
+    }
+}
@@ -1,19 +1,50 @@
 use lsp_server::ErrorCode;
 use lsp_types::{
-    DocumentHighlight, DocumentHighlightKind, DocumentHighlightParams, Position, Range,
+    DocumentHighlight, DocumentHighlightKind, DocumentHighlightParams, LinkedEditingRangeParams,
+    LinkedEditingRanges, Position, Range,
 };
+use tree_sitter::Node;
 
-use crate::document_store;
+use crate::{
+    document_store::{self, Document},
+    parser,
+};
 
 use super::LsError;
 
 /**
- * this highlights occurences of a hovered identifier - not the entire file!
+ * this highlights the matching open and close tag names of a hovered tag - identifiers are not
+ * yet supported!
  */
 pub(crate) fn highlight(
     params: DocumentHighlightParams,
 ) -> Result<Vec<DocumentHighlight>, LsError> {
-    let uri = params.text_document_position_params.text_document.uri;
+    let text_params = params.text_document_position_params;
+    let uri = text_params.text_document.uri;
+    let document = match document_store::get(&uri) {
+        Some(document) => Ok(document),
+        None => document_store::Document::from_uri(&uri)
+            .map(|document| document_store::put(&uri, document))
+            .map_err(|err| {
+                log::error!("failed to read {}: {}", uri, err);
+                return LsError {
+                    message: format!("cannot read file {}", uri),
+                    code: ErrorCode::RequestFailed,
+                };
+            }),
+    }?;
+    return Ok(highlight_tag_names(&document, text_params.position));
+}
+
+/**
+ * links the open and close tag names of the tag under the cursor, so that renaming one renames
+ * the other. self-closing and unclosed tags have nothing to link.
+ */
+pub(crate) fn linked_editing_range(
+    params: LinkedEditingRangeParams,
+) -> Result<Option<LinkedEditingRanges>, LsError> {
+    let text_params = params.text_document_position_params;
+    let uri = text_params.text_document.uri;
     let document = match document_store::get(&uri) {
         Some(document) => Ok(document),
         None => document_store::Document::from_uri(&uri)
@@ -26,20 +57,181 @@ pub(crate) fn highlight(
                 };
             }),
     }?;
-    let _tree = document.tree.root_node();
-    // TODO: implementation!
-    let highlight = DocumentHighlight {
+    return Ok(linked_tag_names(&document, text_params.position));
+}
+
+fn linked_tag_names(document: &Document, position: Position) -> Option<LinkedEditingRanges> {
+    let ranges: Vec<Range> = highlight_tag_names(document, position)
+        .into_iter()
+        .map(|highlight| highlight.range)
+        .collect();
+    return match ranges.len() {
+        2 => Some(LinkedEditingRanges {
+            ranges,
+            word_pattern: None,
+        }),
+        _ => None,
+    };
+}
+
+fn highlight_tag_names(document: &Document, position: Position) -> Vec<DocumentHighlight> {
+    let tag = match parser::find_current_node(&document.tree, position) {
+        Some(node) if node.kind().ends_with("_tag_open") || node.kind().ends_with("_tag_close") => {
+            node.parent()
+        }
+        _ => None,
+    };
+    let tag = match tag {
+        Some(tag) => tag,
+        None => return Vec::new(),
+    };
+    let mut highlights = Vec::new();
+    for child in tag.children(&mut tag.walk()) {
+        match child.kind() {
+            // a missing "</sp:iterator>" has no name to highlight
+            _ if child.is_missing() => {}
+            // "<sp:iterator"
+            kind if kind.ends_with("_tag_open") => {
+                highlights.push(tag_name_highlight(&child, 1, 0))
+            }
+            // "</sp:iterator>"
+            kind if kind.ends_with("_tag_close") => {
+                highlights.push(tag_name_highlight(&child, 2, 1))
+            }
+            _ => {}
+        }
+    }
+    return highlights;
+}
+
+fn tag_name_highlight(node: &Node<'_>, prefix: usize, suffix: usize) -> DocumentHighlight {
+    let start = node.start_position();
+    let end = node.end_position();
+    return DocumentHighlight {
         range: Range {
             start: Position {
-                line: 0,
-                character: 0,
+                line: start.row as u32,
+                character: (start.column + prefix) as u32,
             },
             end: Position {
-                line: 0,
-                character: 0,
+                line: end.row as u32,
+                character: (end.column - suffix) as u32,
             },
         },
         kind: Some(DocumentHighlightKind::TEXT),
     };
-    return Ok(vec![highlight]);
+}
+
+#[cfg(test)]
+mod tests {
+    use lsp_types::{
+        DocumentHighlight, DocumentHighlightKind, LinkedEditingRanges, Position, Range,
+    };
+
+    use crate::document_store::test_document;
+
+    use super::{highlight_tag_names, linked_tag_names};
+
+    fn highlight(line: u32, start: u32, end: u32) -> DocumentHighlight {
+        return DocumentHighlight {
+            range: Range {
+                start: Position {
+                    line,
+                    character: start,
+                },
+                end: Position {
+                    line,
+                    character: end,
+                },
+            },
+            kind: Some(DocumentHighlightKind::TEXT),
+        };
+    }
+
+    #[test]
+    fn test_highlight_tag_with_body() {
+        let document = test_document(concat!(
+            "<sp:iterator collection=\"${_list}\" item=\"_item\">\n",
+            "\t<sp:print name=\"_item\"/>\n",
+            "</sp:iterator>\n",
+        ));
+        let highlights = highlight_tag_names(
+            &document,
+            Position {
+                line: 3,
+                character: 5,
+            },
+        );
+        assert_eq!(highlights, vec![highlight(3, 1, 12), highlight(5, 2, 13)]);
+    }
+
+    #[test]
+    fn test_highlight_self_closing_tag() {
+        let document = test_document("<sp:print name=\"_item\"/>\n");
+        let highlights = highlight_tag_names(
+            &document,
+            Position {
+                line: 3,
+                character: 3,
+            },
+        );
+        assert_eq!(highlights, vec![highlight(3, 1, 9)]);
+    }
+
+    #[test]
+    fn test_highlight_unclosed_tag() {
+        let document = test_document("<sp:iterator collection=\"${_list}\" item=\"_item\">\n");
+        let highlights = highlight_tag_names(
+            &document,
+            Position {
+                line: 3,
+                character: 5,
+            },
+        );
+        assert_eq!(highlights, vec![highlight(3, 1, 12)]);
+    }
+
+    #[test]
+    fn test_linked_tag_names() {
+        let document = test_document(concat!(
+            "<sp:iterator collection=\"${_list}\" item=\"_item\">\n",
+            "\t<sp:print name=\"_item\"/>\n",
+            "</sp:iterator>\n",
+        ));
+        let linked = linked_tag_names(
+            &document,
+            Position {
+                line: 5,
+                character: 4,
+            },
+        );
+        assert_eq!(
+            linked,
+            Some(LinkedEditingRanges {
+                ranges: vec![highlight(3, 1, 12).range, highlight(5, 2, 13).range],
+                word_pattern: None,
+            })
+        );
+        let self_closing = linked_tag_names(
+            &document,
+            Position {
+                line: 4,
+                character: 3,
+            },
+        );
+        assert_eq!(self_closing, None);
+    }
+
+    #[test]
+    fn test_linked_tag_names_of_unclosed_tag() {
+        let document = test_document("<sp:iterator collection=\"${_list}\" item=\"_item\">\n");
+        let linked = linked_tag_names(
+            &document,
+            Position {
+                line: 3,
+                character: 5,
+            },
+        );
+        assert_eq!(linked, None);
+    }
 }
@@ -1,15 +1,15 @@
-use std::{cmp::Ordering, str::FromStr};
+use std::{cmp::Ordering, path::Path, str::FromStr};
 
 use lsp_server::ErrorCode;
-use lsp_types::{Hover, HoverContents, HoverParams, MarkupContent, MarkupKind, Position};
+use lsp_types::{Hover, HoverContents, HoverParams, MarkupContent, MarkupKind, Position, Range};
 use tree_sitter::{Node, Point};
 
-use super::LsError;
+use super::{symbol, LsError};
 
 use crate::{
     document_store,
-    grammar::{self, TagDefinition},
-    parser,
+    grammar::{self, TagAttributeType, TagDefinition},
+    modules, parser,
     spel::{
         self,
         ast::{self, Location, SpelAst, SpelResult},
@@ -39,7 +39,31 @@ pub(crate) fn hover(params: HoverParams) -> Result<Option<Hover>, LsError> {
             ),
             code: ErrorCode::RequestFailed,
         })?;
+    let mut header = Some(node);
+    while let Some(current) = header.filter(|header| header.kind() != "taglib_header") {
+        header = current.parent();
+    }
+    if let Some(header) = header {
+        let attributes = symbol::header_attributes(&header, &document.text);
+        return Ok(
+            hover_taglib(&attributes, &text_params.position).map(|value| Hover {
+                contents: HoverContents::Markup(MarkupContent {
+                    kind: MarkupKind::Markdown,
+                    value,
+                }),
+                range: None,
+            }),
+        );
+    }
+    let module = match node.kind() {
+        "string_content" => module_of_attribute(&node, &document.text),
+        _ => None,
+    };
     return Ok((match node.kind() {
+        "string_content" if module.is_some() => file
+            .to_file_path()
+            .ok()
+            .and_then(|file| hover_module(module.flatten(), &file)),
         "string_content" => {
             let cursor = text_params.position;
             let offset = node.start_position();
@@ -229,31 +253,23 @@ fn hover_object(object: &ast::Object, cursor: &Position, offset: &Point) -> Opti
     return match object {
         // ast::Object::Anchor(_) => todo!(),
         ast::Object::Function(function) => hover_global_function(function, cursor, offset),
-        ast::Object::Name(_interpolation) => None, // TODO
+        ast::Object::Name(_) => Some(hover_object_path(object)),
         // ast::Object::Null(_) => todo!(),
         // ast::Object::String(_) => todo!(),
         ast::Object::FieldAccess {
-            object,
-            field: _field,
+            object: inner,
             dot_location,
-        } => {
-            match compare_cursor_to_location(&dot_location, cursor, offset) {
-                Ordering::Less => hover_object(object, cursor, offset),
-                Ordering::Equal => None,
-                Ordering::Greater => None, // TODO
-            }
+            ..
         }
-        ast::Object::MethodAccess {
-            object,
-            function: _function,
+        | ast::Object::MethodAccess {
+            object: inner,
             dot_location,
-        } => {
-            match compare_cursor_to_location(&dot_location, cursor, offset) {
-                Ordering::Less => hover_object(object, cursor, offset),
-                Ordering::Equal => None,
-                Ordering::Greater => None, // TODO
-            }
-        }
+            ..
+        } => match compare_cursor_to_location(&dot_location, cursor, offset) {
+            Ordering::Less => hover_object(inner, cursor, offset),
+            Ordering::Equal => None,
+            Ordering::Greater => Some(hover_object_path(object)),
+        },
         ast::Object::ArrayAccess {
             object,
             index,
@@ -268,6 +284,36 @@ fn hover_object(object: &ast::Object, cursor: &Position, offset: &Point) -> Opti
     };
 }
 
+fn hover_object_path(object: &ast::Object) -> String {
+    return format!("```spel\n{}\n```", object_path(object));
+}
+
+/**
+ * the dotted path to an object as it was parsed, with arguments and indices left out.
+ */
+fn object_path(object: &ast::Object) -> String {
+    return match object {
+        ast::Object::Anchor(anchor) => anchor.to_string(),
+        ast::Object::Function(function) => function_path(function),
+        ast::Object::Name(name) => name.to_string(),
+        ast::Object::String(string) => string.to_string(),
+        ast::Object::FieldAccess { object, field, .. } => {
+            format!("{}.{}", object_path(object), field)
+        }
+        ast::Object::MethodAccess {
+            object, function, ..
+        } => format!("{}.{}", object_path(object), function_path(function)),
+        ast::Object::ArrayAccess { object, .. } => format!("{}[...]", object_path(object)),
+    };
+}
+
+fn function_path(function: &ast::Function) -> String {
+    return match function.arguments.is_empty() {
+        true => format!("{}()", function.name),
+        false => format!("{}(...)", function.name),
+    };
+}
+
 fn hover_global_function(
     function: &ast::Function,
     cursor: &Position,
@@ -327,6 +373,63 @@ fn hover_uri(_uri: &ast::Uri, _cursor: &Position, _offset: &Point) -> Option<Str
     return None;
 }
 
+/**
+ * for values of `module` attributes the module, and for `uri` attributes without a `module`
+ * attribute the implicit current module (`None`).
+ */
+fn module_of_attribute<'a>(value: &Node, text: &'a str) -> Option<Option<&'a str>> {
+    let attribute = value.parent().and_then(|string| string.parent())?;
+    let tag = attribute.parent()?;
+    let definition = TagDefinition::from_str(tag.kind()).ok()?;
+    let name = parser::attribute_name_of(attribute, text)?;
+    return match definition.attributes.get_by_name(name)?.r#type {
+        TagAttributeType::Module => Some(Some(value.utf8_text(text.as_bytes()).ok()?)),
+        TagAttributeType::Uri { module_attribute } => {
+            match tag
+                .children(&mut tag.walk())
+                .any(|child| parser::attribute_name_of(child, text) == Some(module_attribute))
+            {
+                true => None,
+                false => Some(None),
+            }
+        }
+        _ => None,
+    };
+}
+
+fn hover_module(module: Option<&str>, file: &Path) -> Option<String> {
+    return match module {
+        Some("${module.id}") | None => Some(match modules::find_module_for_file(file) {
+            Some(module) => format!("current module\n\n`{}`", module.path),
+            None => "current module is not listed in the module-file".to_string(),
+        }),
+        Some(name) if name.contains("${") => None,
+        Some(name) => Some(match modules::find_module_by_name(name) {
+            Some(module) => format!("module `{}`\n\n`{}`", name, module.path),
+            None => format!("module `{}` is not listed in the module-file", name),
+        }),
+    };
+}
+
+/**
+ * for the `uri` or `tagdir` value of a taglib header under the cursor, the prefix it binds.
+ */
+fn hover_taglib(attributes: &[(String, String, Range)], cursor: &Position) -> Option<String> {
+    let (name, value, _) = attributes.iter().find(|(name, _, range)| {
+        (name == "uri" || name == "tagdir") && range.start <= *cursor && *cursor <= range.end
+    })?;
+    let prefix = attributes
+        .iter()
+        .find(|(name, _, _)| name == "prefix")
+        .map(|(_, prefix, _)| prefix)?;
+    let description = match (name.as_str(), value.as_str()) {
+        ("uri", "http://www.sitepark.com/taglibs/core") => "the sitepark core tags".to_string(),
+        ("tagdir", directory) => format!("the tag files in `{}`", directory),
+        _ => return Some(format!("binds prefix `{}`", prefix)),
+    };
+    return Some(format!("binds prefix `{}`\n\n{}", prefix, description));
+}
+
 fn find_containing_tag(node: Node<'_>) -> Option<TagDefinition> {
     return node
         .parent()
@@ -345,3 +448,103 @@ fn compare_cursor_to_location(location: &Location, cursor: &Position, offset: &P
     }
     return Ordering::Equal;
 }
+
+#[cfg(test)]
+mod tests {
+    use lsp_types::{Position, Range};
+    use tree_sitter::Point;
+
+    use std::path::Path;
+
+    use crate::{
+        modules::{self, Module},
+        spel::parser::Parser,
+    };
+
+    use super::{hover_module, hover_object, hover_taglib, object_path};
+
+    #[test]
+    fn test_object_path_of_nested_field_access() {
+        let object = Parser::new("_item.properties.get('title').name")
+            .parse_object_ast()
+            .unwrap()
+            .root;
+        assert_eq!(object_path(&object), "_item.properties.get(...).name");
+    }
+
+    #[test]
+    fn test_hover_field_shows_path_up_to_field() {
+        let object = Parser::new("_item.properties.name")
+            .parse_object_ast()
+            .unwrap()
+            .root;
+        let hover = hover_object(
+            &object,
+            &Position {
+                line: 0,
+                character: 10,
+            },
+            &Point { row: 0, column: 0 },
+        );
+        assert_eq!(hover, Some(String::from("```spel\n_item.properties\n```")));
+    }
+
+    #[test]
+    fn test_hover_known_module() {
+        modules::insert_module(
+            "lspml-test-hover-module",
+            Module {
+                path: "/some/modules/hover".to_string(),
+            },
+        );
+        assert_eq!(
+            hover_module(
+                Some("lspml-test-hover-module"),
+                Path::new("/some/other/file.spml")
+            ),
+            Some("module `lspml-test-hover-module`\n\n`/some/modules/hover`".to_string())
+        );
+        assert_eq!(
+            hover_module(None, Path::new("/some/modules/hover/file.spml")),
+            Some("current module\n\n`/some/modules/hover`".to_string())
+        );
+        assert_eq!(
+            hover_module(
+                Some("lspml-test-unknown-module"),
+                Path::new("/some/other/file.spml")
+            ),
+            Some("module `lspml-test-unknown-module` is not listed in the module-file".to_string())
+        );
+    }
+
+    fn header_attribute(name: &str, value: &str, start: u32) -> (String, String, Range) {
+        return (
+            name.to_string(),
+            value.to_string(),
+            Range {
+                start: Position {
+                    line: 1,
+                    character: start,
+                },
+                end: Position {
+                    line: 1,
+                    character: start + value.len() as u32,
+                },
+            },
+        );
+    }
+
+    #[test]
+    fn test_hover_core_taglib_uri() {
+        let attributes = vec![
+            header_attribute("uri", "http://www.sitepark.com/taglibs/core", 18),
+            header_attribute("prefix", "sp", 64),
+        ];
+        let cursor = |character| Position { line: 1, character };
+        assert_eq!(
+            hover_taglib(&attributes, &cursor(30)),
+            Some("binds prefix `sp`\n\nthe sitepark core tags".to_string())
+        );
+        assert_eq!(hover_taglib(&attributes, &cursor(65)), None);
+    }
+}
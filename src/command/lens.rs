@@ -0,0 +1,258 @@
+use std::collections::{HashMap, HashSet};
+
+use lsp_server::ErrorCode;
+use lsp_types::{CodeLens, CodeLensParams, Command, Position, Range, Url};
+use serde_json::Value;
+use tree_sitter::{Node, Point};
+
+use crate::document_store::{self, Document};
+
+use super::{diagnostic, execute, LsError};
+
+/**
+ * places a lens above each sp:set and sp:argument showing how often the declared variable is
+ * referenced within the same document. clicking a lens executes the `lspml.findReferences`
+ * command, which returns the locations of these references.
+ */
+pub(crate) fn lens(params: CodeLensParams) -> Result<Vec<CodeLens>, LsError> {
+    let uri = params.text_document.uri;
+    let document = match document_store::get(&uri) {
+        Some(document) => Ok(document),
+        None => document_store::Document::from_uri(&uri)
+            .map(|document| document_store::put(&uri, document))
+            .map_err(|err| {
+                log::error!("failed to read {}: {}", uri, err);
+                return LsError {
+                    message: format!("cannot read file {}", uri),
+                    code: ErrorCode::RequestFailed,
+                };
+            }),
+    }?;
+    return Ok(reference_lenses(&document, &uri));
+}
+
+/**
+ * the ranges of all references to the variable with the given name within the document.
+ */
+pub(super) fn find_references(document: &Document, name: &str) -> Vec<Range> {
+    let mut declarations = Vec::new();
+    collect_declarations(&document.tree.root_node(), &mut declarations);
+    return references_of(document, &spel_references(document, &declarations), name);
+}
+
+fn reference_lenses(document: &Document, uri: &Url) -> Vec<CodeLens> {
+    let mut declarations = Vec::new();
+    collect_declarations(&document.tree.root_node(), &mut declarations);
+    let spel_references = spel_references(document, &declarations);
+    let mut lenses = Vec::new();
+    for attribute in declarations {
+        let name = match attribute
+            .child(2)
+            .and_then(|string| string.child(1))
+            .filter(|value| value.kind() == "string_content")
+            .and_then(|value| value.utf8_text(document.text.as_bytes()).ok())
+        {
+            Some(name) if !name.contains("${") => name,
+            _ => continue,
+        };
+        let count = references_of(document, &spel_references, name).len();
+        let start = attribute.start_position();
+        let end = attribute.end_position();
+        let range = Range {
+            start: Position {
+                line: start.row as u32,
+                character: start.column as u32,
+            },
+            end: Position {
+                line: end.row as u32,
+                character: end.column as u32,
+            },
+        };
+        lenses.push(CodeLens {
+            range,
+            command: Some(Command {
+                title: match count {
+                    1 => String::from("1 reference"),
+                    count => format!("{} references", count),
+                },
+                command: execute::FIND_REFERENCES.to_string(),
+                arguments: Some(vec![Value::from(uri.as_str()), Value::from(name)]),
+            }),
+            data: None,
+        });
+    }
+    return lenses;
+}
+
+/**
+ * the ranges of all variable names referenced in spel, except for the declarations themselves.
+ */
+fn spel_references(document: &Document, declarations: &[Node]) -> HashMap<String, Vec<Range>> {
+    let declaration_positions: HashSet<Point> = declarations
+        .iter()
+        .filter_map(|attribute| attribute.child(2).and_then(|string| string.child(1)))
+        .map(|value| value.start_position())
+        .collect();
+    let mut references = HashMap::new();
+    for (position, ast) in &document.spel {
+        if declaration_positions.contains(position) {
+            continue;
+        }
+        let mut names = HashMap::new();
+        diagnostic::collect_referenced_names(ast, &mut names);
+        for (name, locations) in names {
+            let ranges: &mut Vec<Range> = references.entry(name.clone()).or_default();
+            for location in locations {
+                let line = position.row as u32 + location.line() as u32;
+                let character = match location.line() {
+                    0 => position.column as u32 + location.char() as u32,
+                    _ => location.char() as u32,
+                };
+                ranges.push(Range {
+                    start: Position { line, character },
+                    end: Position {
+                        line,
+                        character: character + name.len() as u32,
+                    },
+                });
+            }
+        }
+    }
+    return references;
+}
+
+/**
+ * the references found in spel followed by interpolations in plain text and html attributes.
+ */
+fn references_of(
+    document: &Document,
+    spel_references: &HashMap<String, Vec<Range>>,
+    name: &str,
+) -> Vec<Range> {
+    let mut references = spel_references.get(name).cloned().unwrap_or_default();
+    for index in diagnostic::find_interpolations_in_text(&document.text, name) {
+        if is_inside_spel(document, index) {
+            continue;
+        }
+        // skip the "${"
+        let start = index + 2;
+        let line_start = document.text[..start]
+            .rfind('\n')
+            .map_or(0, |index| index + 1);
+        let line = document.text[..start].matches('\n').count() as u32;
+        let character = (start - line_start) as u32;
+        references.push(Range {
+            start: Position { line, character },
+            end: Position {
+                line,
+                character: character + name.len() as u32,
+            },
+        });
+    }
+    references.sort_by_key(|range| range.start);
+    return references;
+}
+
+/**
+ * interpolations inside of attributes that are parsed as spel have already been counted.
+ */
+fn is_inside_spel(document: &Document, index: usize) -> bool {
+    let mut node = document
+        .tree
+        .root_node()
+        .descendant_for_byte_range(index, index);
+    while let Some(current) = node {
+        if current.kind() == "string_content" {
+            return document.spel.contains_key(&current.start_position());
+        }
+        node = current.parent();
+    }
+    return false;
+}
+
+/**
+ * collects the `name` attributes of all sp:set and sp:argument tags.
+ */
+fn collect_declarations<'a>(node: &Node<'a>, declarations: &mut Vec<Node<'a>>) {
+    for child in node.children(&mut node.walk()) {
+        if child.kind() == "set_tag" || child.kind() == "argument_tag" {
+            if let Some(attribute) = child
+                .children(&mut child.walk())
+                .find(|attribute| attribute.kind() == "name_attribute")
+            {
+                declarations.push(attribute);
+            }
+        }
+        collect_declarations(&child, declarations);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lsp_types::{Position, Range, Url};
+    use serde_json::Value;
+
+    use crate::document_store::test_document;
+
+    use super::{find_references, reference_lenses};
+
+    #[test]
+    fn test_reference_count() {
+        let document = test_document(concat!(
+            "<sp:set name=\"_counter\" value=\"1\"/>\n",
+            "<sp:print name=\"_counter\"/>\n",
+            "<sp:if condition=\"${_counter} > 0\">\n",
+            "\t<sp:set name=\"_unused\" value=\"2\"/>\n",
+            "</sp:if>\n",
+        ));
+        let uri = Url::parse("file:///some/test/file.spml").unwrap();
+        let commands: Vec<(String, String, Option<Vec<Value>>)> = reference_lenses(&document, &uri)
+            .into_iter()
+            .filter_map(|lens| lens.command)
+            .map(|command| (command.title, command.command, command.arguments))
+            .collect();
+        assert_eq!(
+            commands,
+            vec![
+                (
+                    String::from("2 references"),
+                    String::from("lspml.findReferences"),
+                    Some(vec![
+                        Value::from("file:///some/test/file.spml"),
+                        Value::from("_counter")
+                    ])
+                ),
+                (
+                    String::from("0 references"),
+                    String::from("lspml.findReferences"),
+                    Some(vec![
+                        Value::from("file:///some/test/file.spml"),
+                        Value::from("_unused")
+                    ])
+                )
+            ]
+        );
+    }
+
+    #[test]
+    fn test_find_references() {
+        let document = test_document(concat!(
+            "<sp:set name=\"_counter\" value=\"1\"/>\n",
+            "<sp:print name=\"_counter\"/>\n",
+            "<div title=\"${_counter}\"></div>\n",
+        ));
+        assert_eq!(
+            find_references(&document, "_counter"),
+            vec![
+                Range {
+                    start: Position::new(4, 16),
+                    end: Position::new(4, 24),
+                },
+                Range {
+                    start: Position::new(5, 14),
+                    end: Position::new(5, 22),
+                },
+            ]
+        );
+    }
+}
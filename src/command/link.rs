@@ -0,0 +1,152 @@
+use std::{path::Path, str::FromStr};
+
+use lsp_server::ErrorCode;
+use lsp_types::{DocumentLink, DocumentLinkParams, Position, Range, Url};
+use tree_sitter::Node;
+
+use crate::{
+    document_store::{self, Document},
+    grammar::{TagAttributeType, TagDefinition},
+    modules, parser,
+};
+
+use super::LsError;
+
+/**
+ * links literal uri attribute values like in `<sp:include uri="/some/file.spml">` to the file they
+ * point to. uris that cannot be resolved are still linked, but without a target.
+ */
+pub(crate) fn link(params: DocumentLinkParams) -> Result<Vec<DocumentLink>, LsError> {
+    let uri = params.text_document.uri;
+    let document = match document_store::get(&uri) {
+        Some(document) => Ok(document),
+        None => document_store::Document::from_uri(&uri)
+            .map(|document| document_store::put(&uri, document))
+            .map_err(|err| {
+                log::error!("failed to read {}: {}", uri, err);
+                return LsError {
+                    message: format!("cannot read file {}", uri),
+                    code: ErrorCode::RequestFailed,
+                };
+            }),
+    }?;
+    let file = uri.to_file_path().map_err(|_| LsError {
+        message: format!("cannot read file {}", uri),
+        code: ErrorCode::RequestFailed,
+    })?;
+    return Ok(document_links(&document, &file));
+}
+
+fn document_links(document: &Document, file: &Path) -> Vec<DocumentLink> {
+    let mut links = Vec::new();
+    collect_links(&document.tree.root_node(), document, file, &mut links);
+    return links;
+}
+
+fn collect_links(node: &Node, document: &Document, file: &Path, links: &mut Vec<DocumentLink>) {
+    for child in node.children(&mut node.walk()) {
+        if let Ok(tag) = TagDefinition::from_str(child.kind()) {
+            links.extend(links_of_tag(&tag, &child, document, file));
+        }
+        collect_links(&child, document, file, links);
+    }
+}
+
+fn links_of_tag(
+    tag: &TagDefinition,
+    node: &Node,
+    document: &Document,
+    file: &Path,
+) -> Vec<DocumentLink> {
+    // in source order, so that the links are too.
+    let attributes: Vec<(&str, Node)> = node
+        .children(&mut node.walk())
+        .filter_map(|child| {
+            parser::attribute_name_of(child, &document.text).map(|name| (name, child))
+        })
+        .collect();
+    let mut links = Vec::new();
+    for (name, attribute) in &attributes {
+        let module_attribute = match tag.attributes.get_by_name(name).map(|a| &a.r#type) {
+            Some(TagAttributeType::Uri { module_attribute }) => module_attribute,
+            _ => continue,
+        };
+        let string = match attribute.child(2) {
+            Some(string) => string,
+            None => continue,
+        };
+        let (start, value) = match parser::string_content_of(string, &document.text) {
+            Some((start, value)) if !value.is_empty() && !value.contains("${") => (start, value),
+            _ => continue,
+        };
+        let end = string
+            .child(string.child_count() - 1)
+            .map(|closing| closing.start_position())
+            .unwrap_or(start);
+        let module = attributes
+            .iter()
+            .find(|(name, _)| name == module_attribute)
+            .and_then(|(_, module)| parser::attribute_value_of(*module, &document.text));
+        let target = modules::resolve_module(module, file)
+            .map(|module| format!("{}{}", module.path, value))
+            .filter(|target| Path::new(target).exists())
+            .and_then(|target| Url::from_file_path(target).ok());
+        links.push(DocumentLink {
+            range: Range {
+                start: Position {
+                    line: start.row as u32,
+                    character: start.column as u32,
+                },
+                end: Position {
+                    line: end.row as u32,
+                    character: end.column as u32,
+                },
+            },
+            target,
+            tooltip: None,
+            data: None,
+        });
+    }
+    return links;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use lsp_types::{Position, Range, Url};
+
+    use crate::document_store::{test_document, TestDirectory, TEST_HEADER};
+
+    use super::document_links;
+
+    #[test]
+    fn test_link_to_included_file() {
+        let mut directory = TestDirectory::new("document-links");
+        directory.register_module();
+        let root = &directory.path;
+        let included = root.join("included.spml");
+        fs::write(&included, TEST_HEADER).unwrap();
+        let document = test_document(concat!(
+            "<sp:include uri=\"/included.spml\"/>\n",
+            "<sp:include uri=\"/missing.spml\"/>\n",
+        ));
+        let links = document_links(&document, &root.join("including.spml"));
+        assert_eq!(links.len(), 2);
+        assert_eq!(
+            links[0].range,
+            Range {
+                start: Position {
+                    line: 3,
+                    character: 17,
+                },
+                end: Position {
+                    line: 3,
+                    character: 31,
+                },
+            }
+        );
+        assert_eq!(links[0].target, Url::from_file_path(&included).ok());
+        assert_eq!(links[1].target, None);
+    }
+}
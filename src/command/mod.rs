@@ -1,18 +1,26 @@
 use anyhow::{Error, Result};
-use lsp_server::{ErrorCode, Message, Request, RequestId, Response, ResponseError};
+use lsp_server::{ErrorCode, Message, Notification, Request, RequestId, Response, ResponseError};
 use lsp_types::{
-    CompletionResponse, DocumentDiagnosticReport, FullDocumentDiagnosticReport,
-    GotoDefinitionResponse, RelatedFullDocumentDiagnosticReport, SemanticTokens,
-    SemanticTokensResult,
+    CompletionResponse, DocumentDiagnosticReport, DocumentSymbolResponse,
+    FullDocumentDiagnosticReport, GotoDefinitionResponse, RelatedFullDocumentDiagnosticReport,
+    SemanticTokens, SemanticTokensResult, WorkspaceEdit, WorkspaceSymbolResponse,
 };
-use std::fmt;
+use std::{fmt, path::PathBuf};
 mod action;
 mod complete;
 mod definition;
 mod diagnostic;
+mod execute;
+mod folding;
+mod format;
+mod hierarchy;
 mod highlight;
 mod hover;
+mod lens;
+mod link;
+mod selection;
 mod semantics;
+mod symbol;
 
 #[derive(Debug)]
 pub(crate) struct LsError {
@@ -101,6 +109,40 @@ pub(crate) fn diagnostic(request: Request) -> Result<Message> {
         .map_err(Error::from);
 }
 
+/**
+ * `textDocument/publishDiagnostics` notifications for all spml files below the given directories.
+ */
+pub(crate) fn diagnose_workspace(roots: &[PathBuf]) -> Result<Vec<Message>> {
+    log::trace!("diagnosing workspace: {roots:?}");
+    return diagnostic::diagnose_all(roots).map(|results| {
+        results
+            .into_iter()
+            .map(|params| {
+                Message::Notification(Notification::new(
+                    "textDocument/publishDiagnostics".to_string(),
+                    params,
+                ))
+            })
+            .collect()
+    });
+}
+
+pub(crate) fn folding(request: Request) -> Result<Message> {
+    log::trace!("got folding range request: {request:?}");
+    return serde_json::from_value(request.params)
+        .map(|params| {
+            Message::Response(match folding::folding(params) {
+                Ok(ranges) => Response {
+                    id: request.id,
+                    result: serde_json::to_value(ranges).ok(),
+                    error: None,
+                },
+                Err(err) => err.to_response(request.id),
+            })
+        })
+        .map_err(Error::from);
+}
+
 pub(crate) fn highlight(request: Request) -> Result<Message> {
     log::trace!("got highlight request: {request:?}");
     return serde_json::from_value(request.params)
@@ -117,6 +159,97 @@ pub(crate) fn highlight(request: Request) -> Result<Message> {
         .map_err(Error::from);
 }
 
+pub(crate) fn lens(request: Request) -> Result<Message> {
+    log::trace!("got code lens request: {request:?}");
+    return serde_json::from_value(request.params)
+        .map(|params| {
+            Message::Response(match lens::lens(params) {
+                Ok(lenses) => Response {
+                    id: request.id,
+                    result: serde_json::to_value(lenses).ok(),
+                    error: None,
+                },
+                Err(err) => err.to_response(request.id),
+            })
+        })
+        .map_err(Error::from);
+}
+
+pub(crate) fn link(request: Request) -> Result<Message> {
+    log::trace!("got document link request: {request:?}");
+    return serde_json::from_value(request.params)
+        .map(|params| {
+            Message::Response(match link::link(params) {
+                Ok(links) => Response {
+                    id: request.id,
+                    result: serde_json::to_value(links).ok(),
+                    error: None,
+                },
+                Err(err) => err.to_response(request.id),
+            })
+        })
+        .map_err(Error::from);
+}
+
+pub(crate) fn on_type_formatting(request: Request) -> Result<Message> {
+    log::trace!("got on type formatting request: {request:?}");
+    return serde_json::from_value(request.params)
+        .map(|params| {
+            Message::Response(match format::on_type_formatting(params) {
+                Ok(edits) => Response {
+                    id: request.id,
+                    result: serde_json::to_value(edits).ok(),
+                    error: None,
+                },
+                Err(err) => err.to_response(request.id),
+            })
+        })
+        .map_err(Error::from);
+}
+
+/**
+ * the response to the command and an edit the client should be asked to apply beforehand.
+ */
+pub(crate) fn execute_command(request: Request) -> Result<(Message, Option<WorkspaceEdit>)> {
+    log::trace!("got execute command request: {request:?}");
+    return serde_json::from_value(request.params)
+        .map(|params| match execute::execute(params) {
+            Ok(execution) => (
+                Message::Response(Response {
+                    id: request.id,
+                    result: Some(execution.result.unwrap_or(serde_json::Value::Null)),
+                    error: None,
+                }),
+                execution.edit,
+            ),
+            Err(err) => (Message::Response(err.to_response(request.id)), None),
+        })
+        .map_err(Error::from);
+}
+
+/**
+ * the commands that can be executed via `workspace/executeCommand`.
+ */
+pub(crate) fn commands() -> Vec<String> {
+    return execute::commands();
+}
+
+pub(crate) fn linked_editing_range(request: Request) -> Result<Message> {
+    log::trace!("got linked editing range request: {request:?}");
+    return serde_json::from_value(request.params)
+        .map(|params| {
+            Message::Response(match highlight::linked_editing_range(params) {
+                Ok(ranges) => Response {
+                    id: request.id,
+                    result: serde_json::to_value(ranges).ok(),
+                    error: None,
+                },
+                Err(err) => err.to_response(request.id),
+            })
+        })
+        .map_err(Error::from);
+}
+
 pub(crate) fn hover(request: Request) -> Result<Option<Message>> {
     log::trace!("got hover request: {request:?}");
     return Ok(
@@ -152,6 +285,102 @@ pub(crate) fn semantics(request: Request) -> Result<Message> {
         .map_err(Error::from);
 }
 
+pub(crate) fn selection(request: Request) -> Result<Message> {
+    log::trace!("got selection range request: {request:?}");
+    return serde_json::from_value(request.params)
+        .map(|params| {
+            Message::Response(match selection::selection(params) {
+                Ok(ranges) => Response {
+                    id: request.id,
+                    result: serde_json::to_value(ranges).ok(),
+                    error: None,
+                },
+                Err(err) => err.to_response(request.id),
+            })
+        })
+        .map_err(Error::from);
+}
+
+pub(crate) fn document_symbol(request: Request) -> Result<Message> {
+    log::trace!("got document symbol request: {request:?}");
+    return serde_json::from_value(request.params)
+        .map(|params| {
+            Message::Response(match symbol::document_symbol(params) {
+                Ok(symbols) => Response {
+                    id: request.id,
+                    result: serde_json::to_value(DocumentSymbolResponse::Nested(symbols)).ok(),
+                    error: None,
+                },
+                Err(err) => err.to_response(request.id),
+            })
+        })
+        .map_err(Error::from);
+}
+
+pub(crate) fn workspace_symbol(request: Request) -> Result<Message> {
+    log::trace!("got workspace symbol request: {request:?}");
+    return serde_json::from_value(request.params)
+        .map(|params| {
+            Message::Response(match symbol::workspace_symbol(params) {
+                Ok(symbols) => Response {
+                    id: request.id,
+                    result: serde_json::to_value(WorkspaceSymbolResponse::Flat(symbols)).ok(),
+                    error: None,
+                },
+                Err(err) => err.to_response(request.id),
+            })
+        })
+        .map_err(Error::from);
+}
+
+pub(crate) fn prepare_call_hierarchy(request: Request) -> Result<Message> {
+    log::trace!("got prepare call hierarchy request: {request:?}");
+    return serde_json::from_value(request.params)
+        .map(|params| {
+            Message::Response(match hierarchy::prepare(params) {
+                Ok(items) => Response {
+                    id: request.id,
+                    result: serde_json::to_value(items).ok(),
+                    error: None,
+                },
+                Err(err) => err.to_response(request.id),
+            })
+        })
+        .map_err(Error::from);
+}
+
+pub(crate) fn incoming_calls(request: Request) -> Result<Message> {
+    log::trace!("got incoming calls request: {request:?}");
+    return serde_json::from_value(request.params)
+        .map(|params| {
+            Message::Response(match hierarchy::incoming(params) {
+                Ok(calls) => Response {
+                    id: request.id,
+                    result: serde_json::to_value(calls).ok(),
+                    error: None,
+                },
+                Err(err) => err.to_response(request.id),
+            })
+        })
+        .map_err(Error::from);
+}
+
+pub(crate) fn outgoing_calls(request: Request) -> Result<Message> {
+    log::trace!("got outgoing calls request: {request:?}");
+    return serde_json::from_value(request.params)
+        .map(|params| {
+            Message::Response(match hierarchy::outgoing(params) {
+                Ok(calls) => Response {
+                    id: request.id,
+                    result: serde_json::to_value(calls).ok(),
+                    error: None,
+                },
+                Err(err) => err.to_response(request.id),
+            })
+        })
+        .map_err(Error::from);
+}
+
 pub(crate) fn action(request: Request) -> Result<Message> {
     log::trace!("got code-action request: {request:?}");
     return serde_json::from_value(request.params)
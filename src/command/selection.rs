@@ -0,0 +1,334 @@
+use lsp_server::ErrorCode;
+use lsp_types::{Position, Range, SelectionRange, SelectionRangeParams};
+use tree_sitter::{Node, Point};
+
+use crate::{
+    document_store::{self, Document},
+    parser,
+    spel::ast::{self, Location, SpelAst, SpelResult},
+};
+
+use super::LsError;
+
+pub(crate) fn selection(params: SelectionRangeParams) -> Result<Vec<SelectionRange>, LsError> {
+    let uri = params.text_document.uri;
+    let document = match document_store::get(&uri) {
+        Some(document) => Ok(document),
+        None => document_store::Document::from_uri(&uri)
+            .map(|document| document_store::put(&uri, document))
+            .map_err(|err| {
+                log::error!("failed to read {}: {}", uri, err);
+                return LsError {
+                    message: format!("cannot read file {}", uri),
+                    code: ErrorCode::RequestFailed,
+                };
+            }),
+    }?;
+    return params
+        .positions
+        .into_iter()
+        .map(|position| {
+            selection_range(&document, position).ok_or_else(|| LsError {
+                message: format!(
+                    "could not determine node in {} at line {}, character {}",
+                    uri, position.line, position.character
+                ),
+                code: ErrorCode::RequestFailed,
+            })
+        })
+        .collect();
+}
+
+fn selection_range(document: &Document, position: Position) -> Option<SelectionRange> {
+    let path = parser::find_node_path(&document.tree, position);
+    let node = *path.last()?;
+    let mut ranges: Vec<Range> = path.iter().map(node_range).collect();
+    ranges.extend(spel_ranges(document, &node, position));
+    let mut selection: Option<SelectionRange> = None;
+    for range in ranges {
+        if selection
+            .as_ref()
+            .is_some_and(|parent| parent.range == range)
+        {
+            continue;
+        }
+        selection = Some(SelectionRange {
+            range,
+            parent: selection.map(Box::new),
+        });
+    }
+    return selection;
+}
+
+/**
+ * the ranges of all spel constructs in the given node that surround the cursor, from the
+ * outermost to the innermost one.
+ */
+pub(super) fn spel_ranges(document: &Document, node: &Node, position: Position) -> Vec<Range> {
+    let mut ranges = Vec::new();
+    if node.kind() != "string_content" {
+        return ranges;
+    }
+    let offset = node.start_position();
+    let mut collector = SpelRangeCollector {
+        cursor: position,
+        offset,
+        ranges: &mut ranges,
+    };
+    match document.spel.get(&offset) {
+        Some(SpelAst::Comparable(SpelResult::Valid(comparable))) => {
+            collector.collect_comparable(comparable)
+        }
+        Some(SpelAst::Condition(SpelResult::Valid(condition))) => {
+            collector.collect_condition(condition)
+        }
+        Some(SpelAst::Expression(SpelResult::Valid(expression))) => {
+            collector.collect_expression(expression)
+        }
+        Some(SpelAst::Object(SpelResult::Valid(object))) => collector.collect_object(object),
+        Some(SpelAst::Uri(SpelResult::Valid(ast::Uri::Object(interpolation)))) => {
+            collector.collect_interpolation(interpolation)
+        }
+        Some(SpelAst::String(SpelResult::Valid(word))) => collector.collect_word(word),
+        _ => {}
+    };
+    return ranges;
+}
+
+/**
+ * collects the ranges of spel constructs surrounding the cursor, from the outermost to the
+ * innermost one.
+ */
+struct SpelRangeCollector<'a> {
+    cursor: Position,
+    offset: Point,
+    ranges: &'a mut Vec<Range>,
+}
+
+impl SpelRangeCollector<'_> {
+    fn collect_object(&mut self, object: &ast::Object) {
+        match object {
+            ast::Object::Anchor(anchor) => {
+                if self.push(
+                    &anchor.opening_bracket_location,
+                    &anchor.closing_bracket_location,
+                ) {
+                    self.collect_word(&anchor.name);
+                }
+            }
+            ast::Object::Function(function) => self.collect_function(function),
+            ast::Object::Name(name) => self.collect_word(name),
+            ast::Object::FieldAccess { object, field, .. } => {
+                self.collect_object(object);
+                self.collect_word(field);
+            }
+            ast::Object::MethodAccess {
+                object, function, ..
+            } => {
+                self.collect_object(object);
+                self.collect_function(function);
+            }
+            ast::Object::ArrayAccess {
+                object,
+                index,
+                opening_bracket_location,
+                closing_bracket_location,
+            } => {
+                self.collect_object(object);
+                if self.push(opening_bracket_location, closing_bracket_location) {
+                    self.collect_expression(index);
+                }
+            }
+            ast::Object::String(_) => {}
+        }
+    }
+
+    fn collect_expression(&mut self, expression: &ast::Expression) {
+        match expression {
+            ast::Expression::Function(function) => self.collect_function(function),
+            ast::Expression::Object(interpolation) => self.collect_interpolation(interpolation),
+            ast::Expression::SignedExpression { expression, .. } => {
+                self.collect_expression(expression)
+            }
+            ast::Expression::BracketedExpression {
+                expression,
+                opening_bracket_location,
+                closing_bracket_location,
+            } => {
+                if self.push(opening_bracket_location, closing_bracket_location) {
+                    self.collect_expression(expression);
+                }
+            }
+            ast::Expression::BinaryOperation { left, right, .. } => {
+                self.collect_expression(left);
+                self.collect_expression(right);
+            }
+            ast::Expression::Ternary {
+                condition,
+                left,
+                right,
+                ..
+            } => {
+                self.collect_condition(condition);
+                self.collect_expression(left);
+                self.collect_expression(right);
+            }
+            _ => {}
+        }
+    }
+
+    fn collect_condition(&mut self, condition: &ast::Condition) {
+        match condition {
+            ast::Condition::Object(interpolation) => self.collect_interpolation(interpolation),
+            ast::Condition::Function(function) => self.collect_function(function),
+            ast::Condition::BracketedCondition {
+                condition,
+                opening_bracket_location,
+                closing_bracket_location,
+            } => {
+                if self.push(opening_bracket_location, closing_bracket_location) {
+                    self.collect_condition(condition);
+                }
+            }
+            ast::Condition::NegatedCondition { condition, .. } => self.collect_condition(condition),
+            ast::Condition::BinaryOperation { left, right, .. } => {
+                self.collect_condition(left);
+                self.collect_condition(right);
+            }
+            ast::Condition::Comparisson { left, right, .. } => {
+                self.collect_comparable(left);
+                self.collect_comparable(right);
+            }
+            _ => {}
+        }
+    }
+
+    fn collect_comparable(&mut self, comparable: &ast::Comparable) {
+        match comparable {
+            ast::Comparable::Condition(condition) => self.collect_condition(condition),
+            ast::Comparable::Expression(expression) => self.collect_expression(expression),
+            ast::Comparable::Function(function) => self.collect_function(function),
+            ast::Comparable::Object(interpolation) => self.collect_interpolation(interpolation),
+            _ => {}
+        }
+    }
+
+    fn collect_function(&mut self, function: &ast::Function) {
+        if self.push(&function.name_location, &function.closing_bracket_location) {
+            for argument in &function.arguments {
+                match &argument.argument {
+                    ast::Argument::Anchor(anchor) => self.collect_word(&anchor.name),
+                    ast::Argument::Function(function) => self.collect_function(function),
+                    ast::Argument::Object(interpolation) => {
+                        self.collect_interpolation(interpolation)
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    fn collect_interpolation(&mut self, interpolation: &ast::Interpolation) {
+        if self.push(
+            &interpolation.opening_bracket_location,
+            &interpolation.closing_bracket_location,
+        ) {
+            self.collect_object(&interpolation.content);
+        }
+    }
+
+    fn collect_word(&mut self, word: &ast::Word) {
+        for fragment in &word.fragments {
+            if let ast::WordFragment::Interpolation(interpolation) = fragment {
+                self.collect_interpolation(interpolation);
+            }
+        }
+    }
+
+    /**
+     * pushes the range between both locations, if it contains the cursor.
+     */
+    fn push(&mut self, start: &Location, end: &Location) -> bool {
+        let range = Range {
+            start: self.position_of(start.line(), start.char()),
+            end: self.position_of(end.line(), end.char() + end.len()),
+        };
+        if range.start > self.cursor || range.end < self.cursor {
+            return false;
+        }
+        self.ranges.push(range);
+        return true;
+    }
+
+    fn position_of(&self, line: u16, character: u16) -> Position {
+        return Position {
+            line: self.offset.row as u32 + line as u32,
+            character: match line {
+                0 => self.offset.column as u32 + character as u32,
+                _ => character as u32,
+            },
+        };
+    }
+}
+
+fn node_range(node: &Node<'_>) -> Range {
+    return Range {
+        start: Position {
+            line: node.start_position().row as u32,
+            character: node.start_position().column as u32,
+        },
+        end: Position {
+            line: node.end_position().row as u32,
+            character: node.end_position().column as u32,
+        },
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use lsp_types::{Position, Range};
+
+    use crate::document_store::test_document;
+
+    use super::selection_range;
+
+    fn range(start: (u32, u32), end: (u32, u32)) -> Range {
+        return Range {
+            start: Position {
+                line: start.0,
+                character: start.1,
+            },
+            end: Position {
+                line: end.0,
+                character: end.1,
+            },
+        };
+    }
+
+    #[test]
+    fn test_selection_range_in_print_name() {
+        let document = test_document("<sp:print name=\"_a\"/>\n");
+        let mut ranges = Vec::new();
+        let mut selection = selection_range(
+            &document,
+            Position {
+                line: 3,
+                character: 17,
+            },
+        );
+        while let Some(current) = selection {
+            ranges.push(current.range);
+            selection = current.parent.map(|parent| *parent);
+        }
+        assert_eq!(
+            ranges,
+            vec![
+                range((3, 16), (3, 18)),
+                range((3, 15), (3, 19)),
+                range((3, 10), (3, 19)),
+                range((3, 0), (3, 21)),
+                range((0, 0), (4, 0)),
+            ]
+        );
+    }
+}
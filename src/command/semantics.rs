@@ -7,7 +7,8 @@ use tree_sitter::{Node, Point};
 
 use crate::{
     document_store,
-    grammar::TagDefinition,
+    grammar::{AttributeRule, TagDefinition},
+    parser,
     spel::ast::{
         Anchor, Argument, Comparable, Condition, Expression, Function, Identifier, Interpolation,
         Location, Null, Number, Object, Query, Regex, SignedNumber, SpelAst, SpelResult,
@@ -202,21 +203,46 @@ fn index_tag(
     spel: &HashMap<Point, SpelAst>,
     tokenizer: &mut Tokenizer,
 ) -> Result<()> {
-    if tag.deprecated {
-        tokenizer.add_node(
-            node,
-            &SemanticTokenType::MACRO,
-            &vec![SemanticTokenModifier::DEPRECATED],
-        );
-    }
     for child in node.children(&mut node.walk()) {
         match child.kind() {
+            // a missing "</sp:worklist>" has no text to highlight
+            _ if child.is_missing() => {}
+            // "<sp:worklist" and "</sp:worklist>"
+            kind if tag.deprecated && kind.ends_with("_tag_open") => tokenizer.add(
+                child.start_position().column as u32 + 1,
+                child.start_position().row as u32,
+                (child.end_byte() - child.start_byte()) as u32 - 1,
+                &SemanticTokenType::MACRO,
+                &vec![SemanticTokenModifier::DEPRECATED],
+            ),
+            kind if tag.deprecated && kind.ends_with("_tag_close") => tokenizer.add(
+                child.start_position().column as u32 + 2,
+                child.start_position().row as u32,
+                (child.end_byte() - child.start_byte()) as u32 - 3,
+                &SemanticTokenType::MACRO,
+                &vec![SemanticTokenModifier::DEPRECATED],
+            ),
             // may need to check on kind of missing child
             "html_void_tag" | "java_tag" | "script_tag" | "style_tag" => {}
+            "ERROR" if parser::is_cdata(child, text) => {}
             "ERROR" | "html_tag" | "html_option_tag" => {
                 index_children(child, text, spel, tokenizer)?
             }
             kind if kind.ends_with("_attribute") => {
+                if let Some(name) = child.child(0).filter(|name| {
+                    name.utf8_text(text.as_bytes()).is_ok_and(|name| {
+                        tag.attribute_rules.iter().any(|rule| match rule {
+                            AttributeRule::Deprecated(deprecated) => *deprecated == name,
+                            _ => false,
+                        })
+                    })
+                }) {
+                    tokenizer.add_node(
+                        name,
+                        &SemanticTokenType::PROPERTY,
+                        &vec![SemanticTokenModifier::DEPRECATED],
+                    );
+                }
                 let value_node = match child.child(2).and_then(|child| child.child(1)) {
                     Some(node) => node,
                     _ => continue,
@@ -276,6 +302,7 @@ fn index_children(
     for child in node.children(&mut node.walk()) {
         match child.kind() {
             "text" | "java_tag" | "html_void_tag" => {}
+            "ERROR" if parser::is_cdata(child, text) => {}
             "ERROR" | "html_tag" | "html_option_tag" | "script_tag" | "style_tag" => {
                 index_children(child, text, spel, tokenizer)?;
             }
@@ -669,11 +696,12 @@ fn index_interpolation(interpolation: &Interpolation, token_collector: &mut Spel
 
 #[cfg(test)]
 mod tests {
-    use lsp_types::{SemanticToken, SemanticTokenType};
+    use lsp_types::{SemanticToken, SemanticTokenModifier, SemanticTokenType};
 
     use crate::{
-        capabilities::TOKEN_TYPES,
+        capabilities::{TOKEN_MODIFIERS, TOKEN_TYPES},
         command::semantics::{SpelTokenCollector, Tokenizer},
+        document_store::test_document,
         spel::ast::{Location, Object, StringLiteral, Word, WordFragment},
     };
 
@@ -786,4 +814,41 @@ mod tests {
             ],
         );
     }
+
+    #[test]
+    fn test_deprecated_tag_name_is_marked() {
+        let document = test_document("<sp:worklist name=\"_list\"/>\n");
+        let tokenizer = &mut Tokenizer::new();
+        crate::command::semantics::index_document(
+            document.tree.root_node(),
+            &document.text,
+            &document.spel,
+            tokenizer,
+        )
+        .unwrap();
+        let deprecated = 1
+            << TOKEN_MODIFIERS
+                .iter()
+                .position(|modifier| *modifier == SemanticTokenModifier::DEPRECATED)
+                .unwrap();
+        let tokens = tokenizer.collect();
+        assert_eq!(tokens[0].delta_line, 3);
+        assert_eq!(tokens[0].delta_start, 1);
+        assert_eq!(tokens[0].length, 11);
+        assert_eq!(tokens[0].token_modifiers_bitset, deprecated);
+    }
+
+    #[test]
+    fn test_unclosed_deprecated_tag() {
+        let document = test_document("<sp:worklist name=\"_list\">\n");
+        let tokenizer = &mut Tokenizer::new();
+        crate::command::semantics::index_document(
+            document.tree.root_node(),
+            &document.text,
+            &document.spel,
+            tokenizer,
+        )
+        .unwrap();
+        assert!(tokenizer.collect().iter().all(|token| token.length < 100));
+    }
 }
@@ -0,0 +1,494 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, OnceLock},
+    time::SystemTime,
+};
+
+use anyhow::Result;
+use lsp_server::ErrorCode;
+use lsp_types::{
+    DocumentSymbol, DocumentSymbolParams, Location, Position, Range, SymbolInformation, SymbolKind,
+    Url, WorkspaceSymbolParams,
+};
+use tree_sitter::{Node, Point};
+
+use crate::{
+    document_store::{self, Document},
+    modules, parser,
+};
+
+use super::LsError;
+
+type SymbolCache = HashMap<PathBuf, (SystemTime, Vec<SymbolInformation>)>;
+
+/**
+ * symbols of already indexed files, keyed by their path and last modification time.
+ */
+fn symbol_cache() -> &'static Arc<Mutex<SymbolCache>> {
+    static SYMBOL_CACHE: OnceLock<Arc<Mutex<SymbolCache>>> = OnceLock::new();
+    return SYMBOL_CACHE.get_or_init(|| Arc::new(Mutex::new(HashMap::new())));
+}
+
+/**
+ * searches all spml files of all known modules for declarations of variables via sp:set or
+ * sp:argument and forms via sp:form.
+ */
+pub(crate) fn workspace_symbol(
+    params: WorkspaceSymbolParams,
+) -> Result<Vec<SymbolInformation>, LsError> {
+    let roots: Vec<PathBuf> = modules::all_modules()
+        .into_iter()
+        .map(|(_, module)| PathBuf::from(module.path))
+        .collect();
+    return find_symbols(&roots, &params.query).map_err(|err| LsError {
+        message: format!("failed to search workspace symbols: {}", err),
+        code: ErrorCode::RequestFailed,
+    });
+}
+
+/**
+ * the headers, the html doctype and the java declarations of the document followed by the same
+ * declarations that are listed as workspace symbols.
+ */
+pub(crate) fn document_symbol(
+    params: DocumentSymbolParams,
+) -> Result<Vec<DocumentSymbol>, LsError> {
+    let uri = params.text_document.uri;
+    let document = match document_store::get(&uri) {
+        Some(document) => Ok(document),
+        None => document_store::Document::from_uri(&uri)
+            .map(|document| document_store::put(&uri, document))
+            .map_err(|err| {
+                log::error!("failed to read {}: {}", uri, err);
+                return LsError {
+                    message: format!("cannot read file {}", uri),
+                    code: ErrorCode::RequestFailed,
+                };
+            }),
+    }?;
+    return Ok(document_symbols(&document));
+}
+
+fn document_symbols(document: &Document) -> Vec<DocumentSymbol> {
+    let root = document.tree.root_node();
+    let mut symbols = Vec::new();
+    for child in root.children(&mut root.walk()) {
+        if parser::is_java_declaration(child, &document.text) {
+            let range = node_range(&child);
+            #[allow(deprecated)]
+            symbols.push(DocumentSymbol {
+                name: String::from("java declaration"),
+                detail: None,
+                kind: SymbolKind::CLASS,
+                tags: None,
+                deprecated: None,
+                range,
+                selection_range: range,
+                children: None,
+            });
+            continue;
+        }
+        if child.kind() == "html_doctype" {
+            let range = node_range(&child);
+            #[allow(deprecated)]
+            symbols.push(DocumentSymbol {
+                name: String::from("doctype"),
+                detail: child
+                    .utf8_text(document.text.as_bytes())
+                    .ok()
+                    .and_then(doctype_content)
+                    .map(String::from),
+                kind: SymbolKind::NAMESPACE,
+                tags: None,
+                deprecated: None,
+                range,
+                selection_range: range,
+                children: None,
+            });
+            continue;
+        }
+        let attributes = header_attributes(&child, &document.text);
+        let name = match child.kind() {
+            "page_header" => String::from("page header"),
+            "import_header" => String::from("import header"),
+            "taglib_header" => match attributes.iter().find(|(name, _, _)| name == "prefix") {
+                Some((_, prefix, _)) => format!("taglib: {}", prefix),
+                None => String::from("taglib"),
+            },
+            _ => continue,
+        };
+        let children = attributes
+            .into_iter()
+            .filter(|(name, _, _)| {
+                ["contentType", "pageEncoding", "import", "uri"].contains(&name.as_str())
+            })
+            .map(|(name, value, range)| {
+                #[allow(deprecated)]
+                return DocumentSymbol {
+                    name,
+                    detail: Some(value),
+                    kind: SymbolKind::PROPERTY,
+                    tags: None,
+                    deprecated: None,
+                    range,
+                    selection_range: range,
+                    children: None,
+                };
+            })
+            .collect();
+        let range = node_range(&child);
+        #[allow(deprecated)]
+        symbols.push(DocumentSymbol {
+            name,
+            detail: None,
+            kind: SymbolKind::NAMESPACE,
+            tags: None,
+            deprecated: None,
+            range,
+            selection_range: range,
+            children: Some(children),
+        });
+    }
+    let mut declarations = Vec::new();
+    collect_declarations(&root, &mut declarations);
+    for (tag, value, kind) in declarations {
+        if let Ok(name) = value.utf8_text(document.text.as_bytes()) {
+            #[allow(deprecated)]
+            symbols.push(DocumentSymbol {
+                name: name.to_string(),
+                detail: Some(format!("sp:{}", tag.kind().trim_end_matches("_tag"))),
+                kind,
+                tags: None,
+                deprecated: None,
+                range: node_range(&tag),
+                selection_range: node_range(&value),
+                children: None,
+            });
+        }
+    }
+    return symbols;
+}
+
+/**
+ * the part of a doctype following the `<!DOCTYPE` keyword, like `html` in `<!DOCTYPE html>`.
+ */
+fn doctype_content(doctype: &str) -> Option<&str> {
+    return doctype
+        .trim_start_matches("<!")
+        .trim_end_matches('>')
+        .trim()
+        .split_once(char::is_whitespace)
+        .map(|(_, content)| content.trim());
+}
+
+/**
+ * headers are not split into attributes by the parser, so `name="value"` pairs are searched for
+ * textually. the ranges span the values.
+ */
+pub(super) fn header_attributes(header: &Node, text: &str) -> Vec<(String, String, Range)> {
+    let header_text = match header.utf8_text(text.as_bytes()) {
+        Ok(header_text) => header_text,
+        Err(_) => return Vec::new(),
+    };
+    let mut attributes = Vec::new();
+    let mut rest = header_text;
+    while let Some(index) = rest.find("=\"") {
+        let name_start = rest[..index]
+            .rfind(|c: char| !c.is_ascii_alphanumeric())
+            .map(|start| start + 1)
+            .unwrap_or(0);
+        let value_start = index + 2;
+        let value_end = match rest[value_start..].find('"') {
+            Some(length) => value_start + length,
+            None => break,
+        };
+        let offset = header_text.len() - rest.len();
+        attributes.push((
+            rest[name_start..index].to_string(),
+            rest[value_start..value_end].to_string(),
+            Range {
+                start: position_in(header, header_text, offset + value_start),
+                end: position_in(header, header_text, offset + value_end),
+            },
+        ));
+        rest = &rest[value_end + 1..];
+    }
+    return attributes;
+}
+
+fn position_in(node: &Node, node_text: &str, offset: usize) -> Position {
+    let start = node.start_position();
+    let preceding = &node_text[..offset];
+    return match preceding.rfind('\n') {
+        Some(newline) => Position {
+            line: (start.row + preceding.matches('\n').count()) as u32,
+            character: (offset - newline - 1) as u32,
+        },
+        None => Position {
+            line: start.row as u32,
+            character: (start.column + offset) as u32,
+        },
+    };
+}
+
+fn node_range(node: &Node) -> Range {
+    return Range {
+        start: point_to_position(node.start_position()),
+        end: point_to_position(node.end_position()),
+    };
+}
+
+fn point_to_position(point: Point) -> Position {
+    return Position {
+        line: point.row as u32,
+        character: point.column as u32,
+    };
+}
+
+fn find_symbols(roots: &[PathBuf], query: &str) -> Result<Vec<SymbolInformation>> {
+    let mut files = Vec::new();
+    for root in roots {
+        collect_spml_files(root, &mut files);
+    }
+    let query = query.to_lowercase();
+    let mut symbols = Vec::new();
+    for file in files {
+        match symbols_of_file(&file) {
+            Ok(symbols_of_file) => symbols.extend(
+                symbols_of_file
+                    .into_iter()
+                    .filter(|symbol| symbol.name.to_lowercase().contains(&query)),
+            ),
+            Err(err) => log::info!("failed to collect symbols of {:?}: {}", file, err),
+        }
+    }
+    return Ok(symbols);
+}
+
+/**
+ * recursively collects all spml files below the given directory. entries that cannot be read are
+ * logged and skipped, symlinked directories are not followed.
+ */
+pub(super) fn collect_spml_files(directory: &Path, files: &mut Vec<PathBuf>) {
+    if !directory.is_dir() {
+        return;
+    }
+    let entries = match fs::read_dir(directory) {
+        Ok(entries) => entries,
+        Err(err) => {
+            log::info!("failed to read directory {:?}: {}", directory, err);
+            return;
+        }
+    };
+    for entry in entries {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(err) => {
+                log::info!("failed to read entry of {:?}: {}", directory, err);
+                continue;
+            }
+        };
+        let path = entry.path();
+        match entry.file_type() {
+            Ok(file_type) if file_type.is_dir() => collect_spml_files(&path, files),
+            Ok(_)
+                if path
+                    .extension()
+                    .is_some_and(|extension| extension == "spml") =>
+            {
+                files.push(path)
+            }
+            Ok(_) => (),
+            Err(err) => log::info!("failed to read file type of {:?}: {}", path, err),
+        }
+    }
+}
+
+fn symbols_of_file(file: &Path) -> Result<Vec<SymbolInformation>> {
+    let modified = fs::metadata(file)?.modified()?;
+    let mut cache = symbol_cache().lock().expect("symbol cache mutex poisoned");
+    if let Some((timestamp, symbols)) = cache.get(file) {
+        if *timestamp == modified {
+            return Ok(symbols.clone());
+        }
+    }
+    let uri = Url::from_file_path(file)
+        .map_err(|_| anyhow::anyhow!("failed to create uri from {:?}", file))?;
+    let document = Document::new(fs::read_to_string(file)?)?;
+    let mut symbols = Vec::new();
+    collect_symbols(
+        &document.tree.root_node(),
+        &document.text,
+        &uri,
+        &mut symbols,
+    );
+    cache.insert(file.to_path_buf(), (modified, symbols.clone()));
+    return Ok(symbols);
+}
+
+fn collect_symbols(node: &Node, text: &str, uri: &Url, symbols: &mut Vec<SymbolInformation>) {
+    let mut declarations = Vec::new();
+    collect_declarations(node, &mut declarations);
+    for (tag, value, kind) in declarations {
+        if let Ok(name) = value.utf8_text(text.as_bytes()) {
+            #[allow(deprecated)]
+            symbols.push(SymbolInformation {
+                name: name.to_string(),
+                kind,
+                tags: None,
+                deprecated: None,
+                location: Location {
+                    uri: uri.clone(),
+                    range: node_range(&value),
+                },
+                container_name: Some(format!("sp:{}", tag.kind().trim_end_matches("_tag"))),
+            });
+        }
+    }
+}
+
+/**
+ * sp:set, sp:argument and sp:form tags together with their literal name values.
+ */
+fn collect_declarations<'a>(
+    node: &Node<'a>,
+    declarations: &mut Vec<(Node<'a>, Node<'a>, SymbolKind)>,
+) {
+    for child in node.children(&mut node.walk()) {
+        let kind = match child.kind() {
+            "set_tag" | "argument_tag" => Some(SymbolKind::VARIABLE),
+            "form_tag" => Some(SymbolKind::OBJECT),
+            _ => None,
+        };
+        if let Some(kind) = kind {
+            let value = child
+                .children(&mut child.walk())
+                .find(|attribute| attribute.kind() == "name_attribute")
+                .and_then(|attribute| attribute.child(2))
+                .and_then(|string| string.child(1))
+                .filter(|value| value.kind() == "string_content");
+            if let Some(value) = value {
+                declarations.push((child, value, kind));
+            }
+        }
+        collect_declarations(&child, declarations);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use lsp_types::{Position, Range};
+
+    use crate::document_store::{test_document, TestDirectory, TEST_HEADER};
+
+    use super::{collect_spml_files, document_symbols, find_symbols};
+
+    #[test]
+    fn test_symbols_in_multiple_files() {
+        let directory = TestDirectory::new("workspace-symbols");
+        let root = &directory.path;
+        fs::create_dir_all(root.join("nested")).unwrap();
+        fs::write(
+            root.join("first.spml"),
+            format!(
+                "{}{}",
+                TEST_HEADER, "<sp:set name=\"_firstCounter\" value=\"1\"/>\n"
+            ),
+        )
+        .unwrap();
+        fs::write(
+            root.join("nested").join("second.spml"),
+            format!(
+                "{}{}",
+                TEST_HEADER,
+                concat!(
+                    "<sp:include uri=\"/first.spml\">\n",
+                    "\t<sp:argument name=\"secondCounter\" value=\"2\"/>\n",
+                    "</sp:include>\n",
+                    "<sp:set name=\"_other\" value=\"3\"/>\n",
+                )
+            ),
+        )
+        .unwrap();
+        let mut names: Vec<String> = find_symbols(std::slice::from_ref(root), "counter")
+            .unwrap()
+            .into_iter()
+            .map(|symbol| symbol.name)
+            .collect();
+        names.sort();
+        assert_eq!(names, vec!["_firstCounter", "secondCounter"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_collect_spml_files_skips_symlinked_directories() {
+        let directory = TestDirectory::new("collect-spml-files");
+        let root = &directory.path;
+        fs::create_dir_all(root.join("nested")).unwrap();
+        fs::write(root.join("nested").join("file.spml"), "").unwrap();
+        fs::write(root.join("other.txt"), "").unwrap();
+        std::os::unix::fs::symlink(root, root.join("nested").join("loop")).unwrap();
+        let mut files = Vec::new();
+        collect_spml_files(root, &mut files);
+        assert_eq!(files, vec![root.join("nested").join("file.spml")]);
+    }
+
+    #[test]
+    fn test_document_symbols_include_headers() {
+        let document = test_document("<sp:set name=\"_counter\" value=\"1\"/>\n");
+        let symbols = document_symbols(&document);
+        let names: Vec<&str> = symbols.iter().map(|symbol| symbol.name.as_str()).collect();
+        assert_eq!(names, vec!["page header", "taglib: sp", "_counter"]);
+        let page_attributes: Vec<&str> = symbols[0]
+            .children
+            .iter()
+            .flatten()
+            .map(|symbol| symbol.name.as_str())
+            .collect();
+        assert_eq!(page_attributes, vec!["pageEncoding", "contentType"]);
+        let taglib_uri = &symbols[1].children.as_ref().unwrap()[0];
+        assert_eq!(taglib_uri.name, "uri");
+        assert_eq!(
+            taglib_uri.range,
+            Range {
+                start: Position {
+                    line: 1,
+                    character: 18,
+                },
+                end: Position {
+                    line: 1,
+                    character: 54,
+                },
+            }
+        );
+    }
+
+    #[test]
+    fn test_document_symbols_include_doctype() {
+        let document = test_document("<!DOCTYPE html>\n<sp:set name=\"_counter\" value=\"1\"/>\n");
+        let symbols = document_symbols(&document);
+        let names: Vec<&str> = symbols.iter().map(|symbol| symbol.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec!["page header", "taglib: sp", "doctype", "_counter"]
+        );
+        assert_eq!(symbols[2].detail.as_deref(), Some("html"));
+        assert_eq!(
+            symbols[2].range,
+            Range {
+                start: Position {
+                    line: 3,
+                    character: 0,
+                },
+                end: Position {
+                    line: 3,
+                    character: 15,
+                },
+            }
+        );
+    }
+}
@@ -0,0 +1,236 @@
+use std::sync::OnceLock;
+
+use anyhow::Result;
+use lsp_types::{Diagnostic, DiagnosticSeverity, DiagnosticTag, NumberOrString};
+
+use crate::{capabilities::CodeActionImplementation, grammar};
+
+#[derive(Debug)]
+pub(crate) struct Config {
+    pub(crate) warn_unused_variables: bool,
+    pub(crate) warn_undefined_variables: bool,
+    pub(crate) strict_html: bool,
+    pub(crate) severity_overrides: Vec<SeverityOverride>,
+    /**
+     * the severity of a page header without a contentType. `None` disables the check.
+     */
+    pub(crate) missing_content_type: Option<DiagnosticSeverity>,
+    /**
+     * the contentType used by generated page headers and the missing contentType quickfix.
+     */
+    pub(crate) content_type: String,
+    pub(crate) page_encoding: String,
+    /**
+     * prefixes of the taglibs imported by generated headers, e.g. "sp" or "spt".
+     */
+    pub(crate) default_taglibs: Vec<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        return Config {
+            warn_unused_variables: false,
+            warn_undefined_variables: false,
+            strict_html: false,
+            severity_overrides: Vec::new(),
+            missing_content_type: Some(DiagnosticSeverity::WARNING),
+            content_type: String::from("text/html; charset=UTF-8"),
+            page_encoding: String::from("UTF-8"),
+            default_taglibs: vec![String::from("sp"), String::from("spt")],
+        };
+    }
+}
+
+/**
+ * the kind of a diagnostic, published as its code. diagnostics that can be fixed by a code action
+ * share the code of that action.
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum DiagnosticType {
+    MissingHeader,
+    SpelSyntaxError,
+    DeprecatedTag,
+    UnusedTaglib,
+    UndeclaredTaglib,
+    MissingContentType,
+    SyntaxError,
+    MisplacedHeader,
+    MisplacedTag,
+    InvalidTagBody,
+    InvalidAttributes,
+    InvalidAttributeValue,
+    DeprecatedAttribute,
+    MissingFile,
+    UnknownModule,
+    UnknownHtmlTag,
+    UndefinedVariable,
+    UnusedVariable,
+    OverwrittenVariable,
+    UnknownInputField,
+    MismatchedTypes,
+    InvalidFunctionCall,
+    InvalidSuppression,
+}
+
+impl DiagnosticType {
+    pub(crate) fn code(&self) -> NumberOrString {
+        return match self {
+            DiagnosticType::MissingHeader => CodeActionImplementation::GENERATE_DEFAULT_HEADER_CODE,
+            DiagnosticType::SpelSyntaxError => CodeActionImplementation::FIX_SPEL_SYNTAX_CODE,
+            DiagnosticType::DeprecatedTag => CodeActionImplementation::REMOVE_DEPRECATED_TAG_CODE,
+            DiagnosticType::UnusedTaglib => CodeActionImplementation::REMOVE_UNUSED_TAGLIB_CODE,
+            DiagnosticType::UndeclaredTaglib => CodeActionImplementation::ADD_MISSING_TAGLIB_CODE,
+            DiagnosticType::MissingContentType => CodeActionImplementation::ADD_CONTENT_TYPE_CODE,
+            DiagnosticType::SyntaxError => NumberOrString::Number(7132),
+            DiagnosticType::MisplacedHeader => NumberOrString::Number(7133),
+            DiagnosticType::MisplacedTag => NumberOrString::Number(7134),
+            DiagnosticType::InvalidTagBody => NumberOrString::Number(7135),
+            DiagnosticType::InvalidAttributes => NumberOrString::Number(7136),
+            DiagnosticType::InvalidAttributeValue => NumberOrString::Number(7137),
+            DiagnosticType::DeprecatedAttribute => NumberOrString::Number(7138),
+            DiagnosticType::MissingFile => NumberOrString::Number(7139),
+            DiagnosticType::UnknownModule => NumberOrString::Number(7140),
+            DiagnosticType::UnknownHtmlTag => NumberOrString::Number(7141),
+            DiagnosticType::UndefinedVariable => NumberOrString::Number(7142),
+            DiagnosticType::UnusedVariable => NumberOrString::Number(7143),
+            DiagnosticType::OverwrittenVariable => NumberOrString::Number(7144),
+            DiagnosticType::UnknownInputField => NumberOrString::Number(7145),
+            DiagnosticType::MismatchedTypes => NumberOrString::Number(7146),
+            DiagnosticType::InvalidFunctionCall => NumberOrString::Number(7147),
+            DiagnosticType::InvalidSuppression => NumberOrString::Number(7148),
+        };
+    }
+}
+
+/**
+ * selects diagnostics either by a tag like "deprecated" or by a type like "UNKNOWN_MODULE".
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum DiagnosticSelector {
+    Tag(DiagnosticTag),
+    Type(DiagnosticType),
+}
+
+impl DiagnosticSelector {
+    pub(crate) fn matches(&self, diagnostic: &Diagnostic) -> bool {
+        return match self {
+            DiagnosticSelector::Tag(tag) => diagnostic
+                .tags
+                .as_ref()
+                .is_some_and(|tags| tags.contains(tag)),
+            DiagnosticSelector::Type(r#type) => diagnostic.code == Some(r#type.code()),
+        };
+    }
+}
+
+/**
+ * changes the severity of all selected diagnostics. a severity of `None` drops them.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct SeverityOverride {
+    pub(crate) selector: DiagnosticSelector,
+    pub(crate) severity: Option<DiagnosticSeverity>,
+}
+
+/**
+ * parses overrides like "deprecated=error" or "UNKNOWN_MODULE=off".
+ */
+pub(crate) fn parse_severity_override(value: &str) -> Result<SeverityOverride> {
+    let (selector, severity) = value
+        .split_once("=")
+        .ok_or_else(|| anyhow::anyhow!("expected <tag or type>=<severity>, got \"{}\"", value))?;
+    let selector = parse_diagnostic_selector(selector)?;
+    let severity = parse_severity(severity)?;
+    return Ok(SeverityOverride { selector, severity });
+}
+
+/**
+ * parses diagnostic tags like "deprecated" as well as diagnostic types like "SYNTAX_ERROR".
+ */
+pub(crate) fn parse_diagnostic_selector(value: &str) -> Result<DiagnosticSelector> {
+    return parse_diagnostic_tag(value)
+        .map(DiagnosticSelector::Tag)
+        .or_else(|_| parse_diagnostic_type(value).map(DiagnosticSelector::Type))
+        .map_err(|_| anyhow::anyhow!("unknown diagnostic tag or type \"{}\"", value));
+}
+
+/**
+ * parses diagnostic tags like "deprecated" or "unnecessary".
+ */
+pub(crate) fn parse_diagnostic_tag(value: &str) -> Result<DiagnosticTag> {
+    return match value {
+        "deprecated" => Ok(DiagnosticTag::DEPRECATED),
+        "unnecessary" => Ok(DiagnosticTag::UNNECESSARY),
+        tag => Err(anyhow::anyhow!("unknown diagnostic tag \"{}\"", tag)),
+    };
+}
+
+/**
+ * parses diagnostic types like "SYNTAX_ERROR" or "UNKNOWN_MODULE".
+ */
+pub(crate) fn parse_diagnostic_type(value: &str) -> Result<DiagnosticType> {
+    return match value {
+        "MISSING_HEADER" => Ok(DiagnosticType::MissingHeader),
+        "SPEL_SYNTAX_ERROR" => Ok(DiagnosticType::SpelSyntaxError),
+        "DEPRECATED_TAG" => Ok(DiagnosticType::DeprecatedTag),
+        "UNUSED_TAGLIB" => Ok(DiagnosticType::UnusedTaglib),
+        "UNDECLARED_TAGLIB" => Ok(DiagnosticType::UndeclaredTaglib),
+        "MISSING_CONTENT_TYPE" => Ok(DiagnosticType::MissingContentType),
+        "SYNTAX_ERROR" => Ok(DiagnosticType::SyntaxError),
+        "MISPLACED_HEADER" => Ok(DiagnosticType::MisplacedHeader),
+        "MISPLACED_TAG" => Ok(DiagnosticType::MisplacedTag),
+        "INVALID_TAG_BODY" => Ok(DiagnosticType::InvalidTagBody),
+        "INVALID_ATTRIBUTES" => Ok(DiagnosticType::InvalidAttributes),
+        "INVALID_ATTRIBUTE_VALUE" => Ok(DiagnosticType::InvalidAttributeValue),
+        "DEPRECATED_ATTRIBUTE" => Ok(DiagnosticType::DeprecatedAttribute),
+        "MISSING_FILE" => Ok(DiagnosticType::MissingFile),
+        "UNKNOWN_MODULE" => Ok(DiagnosticType::UnknownModule),
+        "UNKNOWN_HTML_TAG" => Ok(DiagnosticType::UnknownHtmlTag),
+        "UNDEFINED_VARIABLE" => Ok(DiagnosticType::UndefinedVariable),
+        "UNUSED_VARIABLE" => Ok(DiagnosticType::UnusedVariable),
+        "OVERWRITTEN_VARIABLE" => Ok(DiagnosticType::OverwrittenVariable),
+        "UNKNOWN_INPUT_FIELD" => Ok(DiagnosticType::UnknownInputField),
+        "MISMATCHED_TYPES" => Ok(DiagnosticType::MismatchedTypes),
+        "INVALID_FUNCTION_CALL" => Ok(DiagnosticType::InvalidFunctionCall),
+        "INVALID_SUPPRESSION" => Ok(DiagnosticType::InvalidSuppression),
+        r#type => Err(anyhow::anyhow!("unknown diagnostic type \"{}\"", r#type)),
+    };
+}
+
+/**
+ * parses severities like "error" or "hint". "off" is parsed to `None`.
+ */
+pub(crate) fn parse_severity(value: &str) -> Result<Option<DiagnosticSeverity>> {
+    return match value {
+        "error" => Ok(Some(DiagnosticSeverity::ERROR)),
+        "warning" => Ok(Some(DiagnosticSeverity::WARNING)),
+        "information" => Ok(Some(DiagnosticSeverity::INFORMATION)),
+        "hint" => Ok(Some(DiagnosticSeverity::HINT)),
+        "off" => Ok(None),
+        severity => Err(anyhow::anyhow!("unknown severity \"{}\"", severity)),
+    };
+}
+
+/**
+ * parses the prefix of a taglib a default header can import, which are "sp" and "spt".
+ */
+pub(crate) fn parse_default_taglib(value: &str) -> Result<String> {
+    return match grammar::taglib_import(value) {
+        Some(_) => Ok(value.to_string()),
+        None => Err(anyhow::anyhow!("unknown taglib \"{}\"", value)),
+    };
+}
+
+pub(crate) static CONFIG: OnceLock<Config> = OnceLock::new();
+
+pub(crate) fn init(config: Config) -> Result<()> {
+    CONFIG
+        .set(config)
+        .map_err(|_| anyhow::anyhow!("could not initialize config; already initialized"))?;
+    log::info!("created config: {:?}", CONFIG.get().unwrap());
+    return Ok(());
+}
+
+pub(crate) fn get() -> &'static Config {
+    return CONFIG.get_or_init(Config::default);
+}
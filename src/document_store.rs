@@ -70,6 +70,17 @@ pub(crate) fn put(uri: &Url, document: Document) -> Document {
     return document;
 }
 
+/**
+ * stores the document with the given text, unless the stored one already has the same content,
+ * in which case it is kept as is instead of parsing the text again.
+ */
+pub(crate) fn update(uri: &Url, text: String) -> Result<Document> {
+    if let Some(document) = get(uri).filter(|document| document.text == text) {
+        return Ok(document);
+    }
+    return Document::new(text).map(|document| put(uri, document));
+}
+
 fn collect_spels(root: Node, text: &String) -> HashMap<Point, SpelAst> {
     let mut spels = HashMap::new();
     for node in root.children(&mut root.walk()) {
@@ -95,6 +106,7 @@ fn collect_from_children(node: Node, text: &String, spels: &mut HashMap<Point, S
     for child in node.children(&mut node.walk()) {
         match child.kind() {
             "text" | "java_tag" | "html_void_tag" => {}
+            "ERROR" if parser::is_cdata(child, text) => {}
             "ERROR" | "html_tag" | "html_option_tag" | "script_tag" | "style_tag" => {
                 collect_from_children(child, text, spels);
             }
@@ -119,24 +131,21 @@ fn collect_from_tag(
         match child.kind() {
             // may need to check on kind of missing child
             "html_void_tag" | "java_tag" | "script_tag" | "style_tag" => (),
+            "ERROR" if parser::is_cdata(child, text) => (),
             "ERROR" | "html_tag" | "html_option_tag" => collect_from_children(child, text, spel),
             kind if kind.ends_with("_attribute") => {
                 match parser::attribute_name_of(child, text)
                     .and_then(|attribute| tag.attributes.get_by_name(&attribute))
                 {
                     Some(definition) => {
-                        let (position, text) = match child.child(2).and_then(|child| child.child(1))
+                        let (position, text) = match child
+                            .child(2)
+                            .and_then(|child| parser::string_content_of(child, text))
                         {
-                            Some(node) if node.kind() == "\"" => (node.start_position(), Ok("")),
-                            Some(node) if node.kind() == "string_content" => {
-                                (node.start_position(), node.utf8_text(&text.as_bytes()))
-                            }
+                            Some(content) => content,
                             _ => continue,
                         };
-                        match text
-                            .map_err(Error::from)
-                            .and_then(|text| spel_ast_of(text, &definition.r#type))
-                        {
+                        match spel_ast_of(text, &definition.r#type) {
                             Ok(ast) => {
                                 spel.insert(position, ast);
                             }
@@ -214,3 +223,98 @@ fn spel_ast_of(text: &str, r#type: &TagAttributeType) -> Result<SpelAst> {
         })),
     }
 }
+
+/**
+ * page header and sp taglib import most test documents start with.
+ */
+#[cfg(test)]
+pub(crate) const TEST_HEADER: &str = concat!(
+    "<%@ page language=\"java\" pageEncoding=\"UTF-8\" contentType=\"text/html; charset=UTF-8\"\n",
+    "%><%@ taglib uri=\"http://www.sitepark.com/taglibs/core\" prefix=\"sp\"\n",
+    "%>\n"
+);
+
+/**
+ * parses the test header followed by the given body.
+ */
+#[cfg(test)]
+pub(crate) fn test_document(body: &str) -> Document {
+    return Document::new(format!("{}{}", TEST_HEADER, body)).unwrap();
+}
+
+/**
+ * a uniquely named directory for tests. it is removed again once dropped, together with the
+ * module registered for it.
+ */
+#[cfg(test)]
+pub(crate) struct TestDirectory {
+    pub(crate) path: std::path::PathBuf,
+    module: Option<String>,
+}
+
+#[cfg(test)]
+impl TestDirectory {
+    pub(crate) fn new(name: &str) -> TestDirectory {
+        static COUNTER: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+        let path = std::env::temp_dir().join(format!(
+            "lspml-test-{}-{}-{}",
+            name,
+            std::process::id(),
+            COUNTER.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+        ));
+        fs::create_dir_all(&path).unwrap();
+        return TestDirectory { path, module: None };
+    }
+
+    /**
+     * registers the directory as a module named after it and returns that name.
+     */
+    pub(crate) fn register_module(&mut self) -> String {
+        let name = self.path.file_name().unwrap().to_string_lossy().to_string();
+        crate::modules::insert_module(
+            &name,
+            crate::modules::Module {
+                path: self.path.to_string_lossy().to_string(),
+            },
+        );
+        self.module = Some(name.clone());
+        return name;
+    }
+}
+
+#[cfg(test)]
+impl Drop for TestDirectory {
+    fn drop(&mut self) {
+        if let Some(module) = &self.module {
+            crate::modules::remove_module(module);
+        }
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lsp_types::Url;
+
+    use super::{update, TEST_HEADER};
+
+    fn text() -> String {
+        return format!("{}{}", TEST_HEADER, "<sp:print name=\"_x\"/>\n");
+    }
+
+    #[test]
+    fn test_update_with_unchanged_content_reuses_tree() {
+        let uri = Url::parse("file:///some/test/unchanged.spml").unwrap();
+        let first = update(&uri, text()).unwrap();
+        let second = update(&uri, text()).unwrap();
+        assert_eq!(first.tree.root_node().id(), second.tree.root_node().id());
+    }
+
+    #[test]
+    fn test_update_with_changed_content_reparses() {
+        let uri = Url::parse("file:///some/test/changed.spml").unwrap();
+        let first = update(&uri, text()).unwrap();
+        let second = update(&uri, format!("{}{}", text(), "<sp:print name=\"_y\"/>\n")).unwrap();
+        assert_ne!(first.tree.root_node().id(), second.tree.root_node().id());
+    }
+}
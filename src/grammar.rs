@@ -63,12 +63,19 @@ pub(crate) enum AttributeRule {
     ExactlyOneOf(&'static [&'static str]),
     ExactlyOrBody(&'static str),
     OnlyOneOf(&'static [&'static str]),
+    Conflicts(&'static str, &'static str),
     AtleastOneOf(&'static [&'static str]),
     OnlyWith(&'static str, &'static str),
     OnlyWithEither(&'static str, &'static [&'static str]),
     OnlyWithEitherOrBody(&'static str, &'static [&'static str]),
     Required(&'static str),
+    LocaleValue(&'static str),
+    IdentifierValue(&'static str),
+    NonZeroValue(&'static str),
+    DateFormatValue(&'static str),
+    DecimalFormatValue(&'static str),
     UriExists(&'static str, &'static str),
+    ModuleExists(&'static str),
     ValueOneOf(&'static str, &'static [&'static str]),
     ValueOneOfCaseInsensitive(&'static str, &'static [&'static str]),
     OnlyWithValue(&'static str, &'static str, &'static str),
@@ -78,6 +85,8 @@ pub(crate) enum AttributeRule {
     RequiredWithEitherValue(&'static str, &'static str, &'static [&'static str]),
     ExactlyOneOfOrBody(&'static [&'static str]),
     OnlyOrBody(&'static str),
+    NonEmptyBody,
+    RequiredBody,
     OnlyOneOfOrBody(&'static [&'static str]),
     BodyOnlyWithEitherValue(&'static str, &'static [&'static str]),
     ExactlyOneOfOrBodyWithValue(&'static [&'static str], &'static str, &'static str),
@@ -189,6 +198,7 @@ impl TagDefinition {
             AttributeRule::Required("name"),
             AttributeRule::ExactlyOneOfOrBody(&["value", "expression", "condition", "object"]),
             AttributeRule::OnlyWithEither("default", &["object", "expression"]),
+            AttributeRule::LocaleValue("locale"),
         ]
     );
 
@@ -226,6 +236,7 @@ impl TagDefinition {
             AttributeRule::Required("type"),
             AttributeRule::ValueOneOf("type", &["qrcode"]),
             AttributeRule::ValueOneOf("scope", &["page", "request"]),
+            AttributeRule::LocaleValue("locale"),
         ]
     );
 
@@ -285,6 +296,7 @@ impl TagDefinition {
         rules &[
             AttributeRule::Required("name"),
             AttributeRule::OnlyOrBody("value"),
+            AttributeRule::LocaleValue("locale"),
         ]
     );
 
@@ -388,6 +400,7 @@ impl TagDefinition {
             AttributeRule::OnlyWithEitherValue("index", "action", &["insert", "remove", "replace"]),
             AttributeRule::OnlyWithEither("default", &["object", "expression"]),
             AttributeRule::OnlyWithEither("publisher", &["query", "object"]),
+            AttributeRule::LocaleValue("locale"),
         ]
     );
 
@@ -400,7 +413,7 @@ impl TagDefinition {
             TagDefinition::SP_ELSE,
             TagDefinition::SP_ELSEIF,
         ]),
-        rules &[]
+        rules &[AttributeRule::RequiredBody, AttributeRule::NonEmptyBody]
     );
 
     const SP_DIFF: TagDefinition = tag_definition!(
@@ -419,6 +432,7 @@ impl TagDefinition {
             AttributeRule::Required("from"),
             AttributeRule::Required("to"),
             AttributeRule::OnlyWith("lookup", "locale"), // is that correct?
+            AttributeRule::LocaleValue("locale"),
         ]
     );
 
@@ -466,6 +480,7 @@ impl TagDefinition {
                 "ic",
                 &["eq", "neq", "gt", "gte", "lt", "lte", "contains"],
             ),
+            AttributeRule::LocaleValue("locale"),
         ]
     );
 
@@ -517,6 +532,7 @@ impl TagDefinition {
             AttributeRule::ValueOneOf("type", &["number", "text", "date"]),
             AttributeRule::ValueOneOf("scope", &["page", "request", "session"]),
             AttributeRule::OnlyWithValue("format", "type", "date"),
+            AttributeRule::LocaleValue("locale"),
         ]
     );
 
@@ -536,6 +552,10 @@ impl TagDefinition {
             AttributeRule::Required("index"),
             AttributeRule::Required("from"),
             AttributeRule::ExactlyOneOf(&["to", "condition"]),
+            AttributeRule::NonZeroValue("step"),
+            AttributeRule::LocaleValue("locale"),
+            AttributeRule::RequiredBody,
+            AttributeRule::NonEmptyBody,
         ]
     );
 
@@ -559,12 +579,21 @@ impl TagDefinition {
             ("uri", TagAttributeType::Uri { module_attribute: "module" }),
         rules &[
             AttributeRule::Deprecated("command"),
-            AttributeRule::OnlyOneOf(&["uri", "template"]),
+            AttributeRule::Conflicts("uri", "template"),
             AttributeRule::OnlyWith("module", "uri"),
             AttributeRule::ValueOneOf("nameencoding", &["escff", "hex"]),
-            AttributeRule::ValueOneOf("enctype", &["text/plain", "multipart/form-data"]),
-            AttributeRule::ValueOneOf("method", &["get", "post"]),
+            AttributeRule::ValueOneOf(
+                "enctype",
+                &[
+                    "text/plain",
+                    "multipart/form-data",
+                    "application/x-www-form-urlencoded",
+                ],
+            ),
+            AttributeRule::ValueOneOfCaseInsensitive("method", &["GET", "POST"]),
             AttributeRule::UriExists("uri", "module"),
+            AttributeRule::ModuleExists("module"),
+            AttributeRule::LocaleValue("locale"),
         ]
     );
 
@@ -582,6 +611,7 @@ impl TagDefinition {
         rules &[
             AttributeRule::Required("name"),
             AttributeRule::ExactlyOneOf(&["value", "fixvalue"]),
+            AttributeRule::LocaleValue("locale"),
         ]
     );
 
@@ -621,6 +651,7 @@ impl TagDefinition {
                 "ic",
                 &["eq", "neq", "gt", "gte", "lt", "lte", "contains"],
             ),
+            AttributeRule::LocaleValue("locale"),
         ]
     );
 
@@ -640,11 +671,12 @@ impl TagDefinition {
             ("uri", TagAttributeType::Uri { module_attribute: "module" }),
         rules &[
             AttributeRule::ExactlyOneOf(&["template", "anchor", "uri"]),
-            AttributeRule::OnlyOneOf(&["context", "module"]),
+            AttributeRule::Conflicts("context", "module"),
             AttributeRule::OnlyWith("context", "uri"),
             AttributeRule::OnlyWith("module", "uri"),
             AttributeRule::ValueOneOf("mode", &["in", "out"]),
             AttributeRule::UriExists("uri", "module"),
+            AttributeRule::ModuleExists("module"),
         ]
     );
 
@@ -672,7 +704,12 @@ impl TagDefinition {
             ("item", TagAttributeType::Identifier),
             ("max", TagAttributeType::Expression),
             ("min", TagAttributeType::Expression),
-        rules &[AttributeRule::Required("collection")]
+        rules &[
+            AttributeRule::Required("collection"),
+            AttributeRule::IdentifierValue("item"),
+            AttributeRule::RequiredBody,
+            AttributeRule::NonEmptyBody,
+        ]
     );
 
     const SP_JSON: TagDefinition = tag_definition!(
@@ -692,6 +729,7 @@ impl TagDefinition {
             AttributeRule::ExactlyOrBody("object"),
             AttributeRule::OnlyWith("indent", "object"),
             AttributeRule::OnlyWith("overwrite", "object"),
+            AttributeRule::LocaleValue("locale"),
         ]
     );
 
@@ -724,6 +762,7 @@ impl TagDefinition {
             AttributeRule::OnlyWith("sortsequences", "sortkeys"),
             AttributeRule::OnlyWith("sortkeys", "sortsequences"), // OnlyBoth?
             AttributeRule::OnlyWith("sorttypes", "sortkeys"),
+            AttributeRule::LocaleValue("locale"),
         ]
     );
 
@@ -753,6 +792,7 @@ impl TagDefinition {
             AttributeRule::OnlyWith("sortkeys", "sortsequences"),
             AttributeRule::OnlyWith("sorttypes", "sortkeys"),
             AttributeRule::ValueOneOf("action", &["flip", "open", "close", "expand", "none"]),
+            AttributeRule::LocaleValue("locale"),
         ]
     );
 
@@ -788,6 +828,7 @@ impl TagDefinition {
         rules &[
             AttributeRule::ExactlyOneOf(&["session", "login", "password", "client"]),
             AttributeRule::ValueOneOf("scope", &["windowSession", "browserSession", "application"]),
+            AttributeRule::LocaleValue("locale"),
         ]
     );
 
@@ -805,6 +846,10 @@ impl TagDefinition {
         rules &[
             AttributeRule::ExactlyOneOf(&["collection", "list"]),
             AttributeRule::OnlyWith("separator", "list"),
+            AttributeRule::LocaleValue("locale"),
+            AttributeRule::IdentifierValue("item"),
+            AttributeRule::RequiredBody,
+            AttributeRule::NonEmptyBody,
         ]
     );
 
@@ -870,6 +915,7 @@ impl TagDefinition {
                 "action",
                 &["put", "putNotEmpty", "putAll", "merge"],
             ),
+            AttributeRule::LocaleValue("locale"),
         ]
     );
 
@@ -913,6 +959,7 @@ impl TagDefinition {
             AttributeRule::ValueOneOf("encrypt", &["3des", "aes", "unixcrypt", "md5", "sha"]),
             AttributeRule::ValueOneOf("decrypt", &["3des", "aes"]),
             AttributeRule::OnlyWithEither("cryptkey", &["encrypt", "decrypt"]),
+            AttributeRule::LocaleValue("locale"),
         ]
     );
 
@@ -951,8 +998,11 @@ impl TagDefinition {
             AttributeRule::OnlyWithEitherOrBody("default", &["name", "expression"]),
             AttributeRule::OnlyOneOf(&["convert", "encoding", "decoding", "encrypt", "decrypt"]),
             AttributeRule::OnlyWithEither("cryptkey", &["encrypt", "decrypt"]),
-            AttributeRule::OnlyOneOf(&["dateformat", "decimalformat"]),
+            AttributeRule::Conflicts("dateformat", "decimalformat"),
             AttributeRule::OnlyWith("arg", "text"),
+            AttributeRule::LocaleValue("locale"),
+            AttributeRule::DateFormatValue("dateformat"),
+            AttributeRule::DecimalFormatValue("decimalformat"),
         ]
     );
 
@@ -977,7 +1027,10 @@ impl TagDefinition {
             ("readonly", TagAttributeType::Condition),
             ("type", TagAttributeType::String),
             ("value", TagAttributeType::String),
-        rules &[AttributeRule::Required("name")]
+        rules &[
+            AttributeRule::Required("name"),
+            AttributeRule::LocaleValue("locale"),
+        ]
     );
 
     const SP_RANGE: TagDefinition = tag_definition!(
@@ -1013,6 +1066,7 @@ impl TagDefinition {
         rules &[
             AttributeRule::ExactlyOneOfOrBody(&["value", "expression", "condition", "object"]),
             AttributeRule::OnlyWithEitherOrBody("default", &["object", "expression"]),
+            AttributeRule::LocaleValue("locale"),
         ]
     );
 
@@ -1054,7 +1108,9 @@ impl TagDefinition {
             AttributeRule::AtleastOneOf(&["height", "width"]),
             AttributeRule::Deprecated("scalesteps"),
             AttributeRule::ValueOneOf("padding", &["on", "off", "fit", "fit/no"]),
+            AttributeRule::OnlyWithEitherValue("background", "padding", &["on", "fit", "fit/no"]),
             AttributeRule::ValueOneOf("scope", &["page", "request"]),
+            AttributeRule::LocaleValue("locale"),
         ]
     );
 
@@ -1090,7 +1146,10 @@ impl TagDefinition {
             ("multiple", TagAttributeType::Condition),
             ("name", TagAttributeType::Identifier),
             ("type", TagAttributeType::String),
-        rules &[AttributeRule::Required("name")]
+        rules &[
+            AttributeRule::Required("name"),
+            AttributeRule::LocaleValue("locale"),
+        ]
     );
 
     const SP_SET: TagDefinition = tag_definition!(
@@ -1114,10 +1173,14 @@ impl TagDefinition {
             AttributeRule::Required("name"),
             AttributeRule::ExactlyOneOfOrBody(&["value", "expression", "condition", "object"]),
             AttributeRule::OnlyWithEitherOrBody("default", &["object", "expression"]),
-            AttributeRule::OnlyOneOf(&["overwrite", "insert"]),
+            AttributeRule::Conflicts("overwrite", "insert"),
+            // insert is useless without a value, expression, object or body to insert
+            AttributeRule::OnlyWithEitherOrBody("insert", &["value", "expression", "object"]),
             AttributeRule::ValueOneOf("scope", &["page", "request"]),
             AttributeRule::ValueOneOf("insert", &["replace", "append", "prepend"]),
             AttributeRule::ValueOneOf("contentType", &["json"]),
+            AttributeRule::LocaleValue("locale"),
+            AttributeRule::IdentifierValue("name"),
         ]
     );
 
@@ -1138,6 +1201,7 @@ impl TagDefinition {
             AttributeRule::Required("name"),
             AttributeRule::Required("collection"),
             AttributeRule::ValueOneOf("scope", &["page", "request", "session"]),
+            AttributeRule::LocaleValue("locale"),
         ]
     );
 
@@ -1180,9 +1244,10 @@ impl TagDefinition {
             ("value", TagAttributeType::String),
         rules &[
             AttributeRule::Required("name"),
-            AttributeRule::OnlyOneOf(&["value", "fixvalue"]),
+            AttributeRule::Conflicts("value", "fixvalue"),
             AttributeRule::ValueOneOf("type", &["date", "email", "number", "text", "url"]),
             AttributeRule::OnlyWithEitherValue("format", "type", &["date", "number"]),
+            AttributeRule::LocaleValue("locale"),
         ]
     );
 
@@ -1201,7 +1266,8 @@ impl TagDefinition {
             ("value", TagAttributeType::String),
         rules &[
             AttributeRule::Required("name"),
-            AttributeRule::OnlyOneOf(&["value", "fixvalue"]),
+            AttributeRule::Conflicts("value", "fixvalue"),
+            AttributeRule::LocaleValue("locale"),
         ]
     );
 
@@ -1227,7 +1293,7 @@ impl TagDefinition {
         rules &[
             AttributeRule::Required("name"),
             AttributeRule::Required("text"),
-            AttributeRule::OnlyOneOf(&["value", "fixvalue"]),
+            AttributeRule::Conflicts("value", "fixvalue"),
             AttributeRule::ValueOneOf("fontstyle", &["plain", "bold", "italic"]),
             AttributeRule::ValueOneOf(
                 "gravity",
@@ -1253,6 +1319,7 @@ impl TagDefinition {
                 ],
             ),
             AttributeRule::ValueOneOf("scope", &["page", "request"]),
+            AttributeRule::LocaleValue("locale"),
         ]
     );
 
@@ -1281,7 +1348,8 @@ impl TagDefinition {
             ("value", TagAttributeType::String),
         rules &[
             AttributeRule::Required("name"),
-            AttributeRule::OnlyOneOf(&["value", "fixvalue"]),
+            AttributeRule::Conflicts("value", "fixvalue"),
+            AttributeRule::LocaleValue("locale"),
         ]
     );
 
@@ -1293,7 +1361,10 @@ impl TagDefinition {
         attributes
             ("locale", TagAttributeType::Object),
             ("name", TagAttributeType::Identifier),
-        rules &[AttributeRule::Required("name")]
+        rules &[
+            AttributeRule::Required("name"),
+            AttributeRule::LocaleValue("locale"),
+        ]
     );
 
     const SP_URL: TagDefinition = tag_definition!(
@@ -1321,10 +1392,12 @@ impl TagDefinition {
             AttributeRule::Deprecated("absolute"),
             AttributeRule::Deprecated("gui"),
             AttributeRule::ExactlyOneOf(&["uri", "template", "command", "information"]),
-            AttributeRule::OnlyOneOf(&["context", "module"]),
+            AttributeRule::Conflicts("context", "module"),
             AttributeRule::OnlyWith("context", "uri"),
             AttributeRule::OnlyWith("module", "uri"),
             AttributeRule::UriExists("uri", "module"),
+            AttributeRule::ModuleExists("module"),
+            AttributeRule::LocaleValue("locale"),
         ]
     );
 
@@ -1398,8 +1471,9 @@ impl TagDefinition {
             ("value", TagAttributeType::String),
         rules &[
             AttributeRule::Required("name"),
-            AttributeRule::OnlyOneOf(&["value", "fixvalue"]),
+            AttributeRule::Conflicts("value", "fixvalue"),
             AttributeRule::ValueOneOf("type", &["date", "datetime"]),
+            AttributeRule::LocaleValue("locale"),
         ]
     );
 
@@ -1507,7 +1581,10 @@ impl TagDefinition {
         attributes
             ("locale", TagAttributeType::Object),
             ("name", TagAttributeType::Identifier),
-        rules &[AttributeRule::Required("name")]
+        rules &[
+            AttributeRule::Required("name"),
+            AttributeRule::LocaleValue("locale"),
+        ]
     );
 
     const SPT_ID2URL: TagDefinition = tag_definition!(
@@ -1527,6 +1604,7 @@ impl TagDefinition {
             AttributeRule::Required("object"),
             AttributeRule::Required("querystring"),
             AttributeRule::ValueOneOf("url", &["relative", "absolute"]),
+            AttributeRule::LocaleValue("locale"),
         ]
     );
 
@@ -1555,7 +1633,7 @@ impl TagDefinition {
             ("name", TagAttributeType::Identifier),
             ("object", TagAttributeType::Object),
             ("width", TagAttributeType::Expression),
-        rules &[]
+        rules &[AttributeRule::LocaleValue("locale")]
     );
 
     const SPT_IMP: TagDefinition = tag_definition!(
@@ -1686,7 +1764,7 @@ impl TagDefinition {
             ("width", TagAttributeType::Expression),
         rules &[
             AttributeRule::Required("name"),
-            AttributeRule::OnlyOneOf(&["value", "fixvalue"]),
+            AttributeRule::Conflicts("value", "fixvalue"),
             AttributeRule::OnlyWith("filterattribute", "filter"),
             AttributeRule::OnlyWith("filteric", "filter"),
             AttributeRule::OnlyWith("filterinvert", "filter"),
@@ -1698,6 +1776,7 @@ impl TagDefinition {
             AttributeRule::ValueOneOf("filtermode", &["simple", "regex"]),
             AttributeRule::OnlyWithValue("height", "type", "image"),
             AttributeRule::OnlyWithValue("width", "type", "image"),
+            AttributeRule::LocaleValue("locale"),
         ]
     );
 
@@ -1717,7 +1796,8 @@ impl TagDefinition {
             ("value", TagAttributeType::String),
         rules &[
             AttributeRule::Required("name"),
-            AttributeRule::OnlyOneOf(&["value", "fixvalue"]),
+            AttributeRule::Conflicts("value", "fixvalue"),
+            AttributeRule::LocaleValue("locale"),
         ]
     );
 
@@ -1794,9 +1874,10 @@ impl TagDefinition {
             ("value", TagAttributeType::String),
         rules &[
             AttributeRule::Required("name"),
-            AttributeRule::OnlyOneOf(&["value", "fixvalue"]),
+            AttributeRule::Conflicts("value", "fixvalue"),
             AttributeRule::ValueOneOf("type", &["date", "email", "number", "text", "url"]),
             AttributeRule::OnlyWithEitherValue("format", "type", &["date", "number"]),
+            AttributeRule::LocaleValue("locale"),
         ]
     );
 
@@ -1820,7 +1901,8 @@ impl TagDefinition {
             ("value", TagAttributeType::String),
         rules &[
             AttributeRule::Required("name"),
-            AttributeRule::OnlyOneOf(&["value", "fixvalue"]),
+            AttributeRule::Conflicts("value", "fixvalue"),
+            AttributeRule::LocaleValue("locale"),
         ]
     );
 
@@ -1831,7 +1913,10 @@ impl TagDefinition {
         children TagChildren::None,
         attributes
             ("connect", TagAttributeType::Identifier),
-        rules &[AttributeRule::Required("connect")]
+        rules &[
+            AttributeRule::Required("connect"),
+            AttributeRule::IdentifierValue("connect"),
+        ]
     );
 
     const SPT_TINYMCE: TagDefinition = tag_definition!(
@@ -1856,7 +1941,7 @@ impl TagDefinition {
             ("value", TagAttributeType::String),
         rules &[
             AttributeRule::Required("name"),
-            AttributeRule::OnlyOneOf(&["value", "fixvalue"]),
+            AttributeRule::Conflicts("value", "fixvalue"),
             AttributeRule::ValueOneOf("theme", &["simple", "advanced"]),
             AttributeRule::ValueOneOf("toggle", &["true", "false", "auto"]),
         ]
@@ -1873,7 +1958,10 @@ impl TagDefinition {
             ("name", TagAttributeType::Identifier),
             ("to", TagAttributeType::Expression),
             ("value", TagAttributeType::String),
-        rules &[AttributeRule::Required("name")]
+        rules &[
+            AttributeRule::Required("name"),
+            AttributeRule::LocaleValue("locale"),
+        ]
     );
 
     const SPT_UPLOAD: TagDefinition = tag_definition!(
@@ -1885,7 +1973,10 @@ impl TagDefinition {
             ("locale", TagAttributeType::Object),
             ("name", TagAttributeType::Identifier),
             ("previewimage", TagAttributeType::Condition),
-        rules &[AttributeRule::Required("name")]
+        rules &[
+            AttributeRule::Required("name"),
+            AttributeRule::LocaleValue("locale"),
+        ]
     );
 
     const SPT_WORKLIST: TagDefinition = tag_definition!(
@@ -1986,6 +2077,17 @@ pub(crate) const TOP_LEVEL_TAGS: [TagDefinition; 78] = [
     TagDefinition::SPT_WORKLIST,
 ];
 
+/**
+ * the import of a well known taglib, as it is written by the default header.
+ */
+pub(crate) fn taglib_import(prefix: &str) -> Option<&'static str> {
+    return match prefix {
+        "sp" => Some("<%@ taglib uri=\"http://www.sitepark.com/taglibs/core\" prefix=\"sp\"\n%>"),
+        "spt" => Some("<%@ taglib tagdir=\"/WEB-INF/tags/spt\" prefix=\"spt\"\n%>"),
+        _ => None,
+    };
+}
+
 impl FromStr for TagDefinition {
     type Err = Error;
 
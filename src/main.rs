@@ -1,16 +1,26 @@
-use std::{error::Error, fs::File};
+use std::{
+    error::Error,
+    fs::{self, File},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicI32, Ordering},
+    thread,
+};
 
 use anyhow::Result;
 use clap::Parser;
-use lsp_server::{Connection, Message};
+use lsp_server::{Connection, Message, Request, RequestId};
 use lsp_types::{
-    CancelParams, DidChangeTextDocumentParams, DidCloseTextDocumentParams,
-    DidOpenTextDocumentParams, DidSaveTextDocumentParams, InitializeParams,
+    ApplyWorkspaceEditParams, CancelParams, DidChangeTextDocumentParams,
+    DidChangeWatchedFilesParams, DidChangeWatchedFilesRegistrationOptions,
+    DidCloseTextDocumentParams, DidOpenTextDocumentParams, DidSaveTextDocumentParams,
+    FileSystemWatcher, GlobPattern, InitializeParams, Registration, RegistrationParams,
+    WorkspaceEdit,
 };
 use structured_logger::Builder;
 
 mod capabilities;
 mod command;
+mod config;
 mod document_store;
 mod grammar;
 mod modules;
@@ -24,8 +34,28 @@ struct CommandLineOpts {
     log_file: Option<String>,
     #[clap(long, default_value = "INFO")]
     log_level: String,
+    #[clap(long = "modules-file")]
+    modules_files: Vec<String>,
+    #[clap(long)]
+    warn_unused_variables: bool,
     #[clap(long)]
-    modules_file: Option<String>,
+    warn_undefined_variables: bool,
+    #[clap(long)]
+    strict_html: bool,
+    #[clap(long = "severity-override", value_parser = config::parse_severity_override)]
+    severity_overrides: Vec<config::SeverityOverride>,
+    #[clap(long, default_value = "warning")]
+    missing_content_type: String,
+    #[clap(long, default_value = "text/html; charset=UTF-8")]
+    content_type: String,
+    #[clap(long, default_value = "UTF-8")]
+    page_encoding: String,
+    #[clap(
+        long = "default-taglib",
+        default_values = ["sp", "spt"],
+        value_parser = config::parse_default_taglib
+    )]
+    default_taglibs: Vec<String>,
 }
 
 fn main() -> Result<(), Box<dyn Error + Sync + Send>> {
@@ -43,10 +73,20 @@ fn main() -> Result<(), Box<dyn Error + Sync + Send>> {
         .init();
     log::info!("lspml starting...");
     log::debug!("commandline opts: {:?}", opts);
-    match opts.modules_file {
-        Some(file) => modules::init_module_mappings_from_file(&file),
-        None => modules::init_empty_module_mappings(),
+    match opts.modules_files.is_empty() {
+        true => modules::init_empty_module_mappings(),
+        false => modules::init_module_mappings_from_files(&opts.modules_files),
     }?;
+    config::init(config::Config {
+        warn_unused_variables: opts.warn_unused_variables,
+        warn_undefined_variables: opts.warn_undefined_variables,
+        strict_html: opts.strict_html,
+        severity_overrides: opts.severity_overrides,
+        missing_content_type: config::parse_severity(&opts.missing_content_type)?,
+        content_type: opts.content_type,
+        page_encoding: opts.page_encoding,
+        default_taglibs: opts.default_taglibs,
+    })?;
 
     let (connection, io_threads) = Connection::stdio();
     let server_capabilities = serde_json::to_value(capabilities::create())?;
@@ -60,6 +100,9 @@ fn main() -> Result<(), Box<dyn Error + Sync + Send>> {
         }
     };
 
+    if !modules::module_files().is_empty() && supports_watching_files(&initialization_params) {
+        watch_module_files(&connection)?;
+    }
     main_loop(connection, initialization_params)?;
     io_threads.join()?;
 
@@ -69,9 +112,29 @@ fn main() -> Result<(), Box<dyn Error + Sync + Send>> {
 
 fn main_loop(
     connection: Connection,
-    _initialization_params: InitializeParams,
+    initialization_params: InitializeParams,
 ) -> Result<(), Box<dyn Error + Sync + Send>> {
     log::info!("server started");
+    if diagnose_workspace_enabled(&initialization_params) {
+        // scanning the workspace can take a while, which must not block answering requests.
+        let sender = connection.sender.clone();
+        let roots = workspace_roots(&initialization_params);
+        thread::spawn(move || {
+            let messages = match command::diagnose_workspace(&roots) {
+                Ok(messages) => messages,
+                Err(err) => {
+                    log::error!("failed to diagnose workspace: {}", err);
+                    return;
+                }
+            };
+            for message in messages {
+                if let Err(err) = sender.send(message) {
+                    log::error!("failed to publish workspace diagnostics: {}", err);
+                    return;
+                }
+            }
+        });
+    }
 
     for message in &connection.receiver {
         match message {
@@ -83,10 +146,35 @@ fn main_loop(
                     "textDocument/completion" => command::complete(request).map(Some),
                     "textDocument/definition" => command::definition(request).map(Some),
                     "textDocument/diagnostic" => command::diagnostic(request).map(Some),
-                    "textDocument/documentHighlight" => command::highlight(request).map(Some), // stub
+                    "textDocument/documentHighlight" => command::highlight(request).map(Some),
+                    "textDocument/foldingRange" => command::folding(request).map(Some),
                     "textDocument/semanticTokens/full" => command::semantics(request).map(Some),
                     "textDocument/codeAction" => command::action(request).map(Some),
+                    "textDocument/codeLens" => command::lens(request).map(Some),
+                    "textDocument/documentLink" => command::link(request).map(Some),
+                    "textDocument/onTypeFormatting" => {
+                        command::on_type_formatting(request).map(Some)
+                    }
+                    "textDocument/linkedEditingRange" => {
+                        command::linked_editing_range(request).map(Some)
+                    }
                     "textDocument/hover" => command::hover(request),
+                    "textDocument/selectionRange" => command::selection(request).map(Some),
+                    "textDocument/documentSymbol" => command::document_symbol(request).map(Some),
+                    "workspace/symbol" => command::workspace_symbol(request).map(Some),
+                    "workspace/executeCommand" => {
+                        command::execute_command(request).and_then(|(response, edit)| {
+                            if let Some(edit) = edit {
+                                apply_edit(&connection, edit)?;
+                            }
+                            return Ok(Some(response));
+                        })
+                    }
+                    "textDocument/prepareCallHierarchy" => {
+                        command::prepare_call_hierarchy(request).map(Some)
+                    }
+                    "callHierarchy/incomingCalls" => command::incoming_calls(request).map(Some),
+                    "callHierarchy/outgoingCalls" => command::outgoing_calls(request).map(Some),
                     _ => command::unknown(request).map(Some),
                 }
                 .and_then(|response| match response {
@@ -113,6 +201,13 @@ fn main_loop(
                 "textDocument/didClose" => {
                     closed(serde_json::from_value(notification.params)?)?;
                 }
+                "workspace/didChangeWatchedFiles" => {
+                    watched_files_changed(
+                        &connection,
+                        serde_json::from_value(notification.params)?,
+                        supports_diagnostic_refresh(&initialization_params),
+                    )?;
+                }
                 "$/cancelRequest" => {
                     let params: CancelParams = serde_json::from_value(notification.params).unwrap();
                     log::debug!("attempted to cancel request {:?}", params.id);
@@ -125,11 +220,137 @@ fn main_loop(
     return Ok(());
 }
 
+fn send_request(connection: &Connection, method: &str, params: serde_json::Value) -> Result<()> {
+    static NEXT_REQUEST_ID: AtomicI32 = AtomicI32::new(0);
+    return connection
+        .sender
+        .send(Message::Request(Request {
+            id: RequestId::from(NEXT_REQUEST_ID.fetch_add(1, Ordering::Relaxed)),
+            method: method.to_string(),
+            params,
+        }))
+        .map_err(|err| anyhow::anyhow!(err));
+}
+
+/**
+ * publishing the diagnostics of all workspace files on startup is opt-in via the initialization
+ * option `diagnoseWorkspace`, as it may take a while in large repositories.
+ */
+fn diagnose_workspace_enabled(params: &InitializeParams) -> bool {
+    return params
+        .initialization_options
+        .as_ref()
+        .and_then(|options| options.get("diagnoseWorkspace"))
+        .and_then(|enabled| enabled.as_bool())
+        .unwrap_or(false);
+}
+
+fn workspace_roots(params: &InitializeParams) -> Vec<PathBuf> {
+    #[allow(deprecated)]
+    let uris = match &params.workspace_folders {
+        Some(folders) => folders.iter().map(|folder| folder.uri.clone()).collect(),
+        None => params.root_uri.iter().cloned().collect::<Vec<_>>(),
+    };
+    return uris
+        .into_iter()
+        .filter_map(|uri| uri.to_file_path().ok())
+        .collect();
+}
+
+fn supports_watching_files(params: &InitializeParams) -> bool {
+    return params
+        .capabilities
+        .workspace
+        .as_ref()
+        .and_then(|workspace| workspace.did_change_watched_files.as_ref())
+        .and_then(|capabilities| capabilities.dynamic_registration)
+        .unwrap_or(false);
+}
+
+fn supports_diagnostic_refresh(params: &InitializeParams) -> bool {
+    return params
+        .capabilities
+        .workspace
+        .as_ref()
+        .and_then(|workspace| workspace.diagnostic.as_ref())
+        .and_then(|capabilities| capabilities.refresh_support)
+        .unwrap_or(false);
+}
+
+fn apply_edit(connection: &Connection, edit: WorkspaceEdit) -> Result<()> {
+    return send_request(
+        connection,
+        "workspace/applyEdit",
+        serde_json::to_value(ApplyWorkspaceEditParams { label: None, edit })?,
+    );
+}
+
+fn watch_module_files(connection: &Connection) -> Result<()> {
+    let watchers = modules::module_files()
+        .iter()
+        .map(|file| FileSystemWatcher {
+            glob_pattern: GlobPattern::String(
+                fs::canonicalize(file)
+                    .map(|path| path.to_string_lossy().to_string())
+                    .unwrap_or_else(|_| file.to_string()),
+            ),
+            kind: None,
+        })
+        .collect();
+    return send_request(
+        connection,
+        "client/registerCapability",
+        serde_json::to_value(RegistrationParams {
+            registrations: vec![Registration {
+                id: "module-files".to_string(),
+                method: "workspace/didChangeWatchedFiles".to_string(),
+                register_options: serde_json::to_value(DidChangeWatchedFilesRegistrationOptions {
+                    watchers,
+                })
+                .ok(),
+            }],
+        })?,
+    );
+}
+
+fn watched_files_changed(
+    connection: &Connection,
+    params: DidChangeWatchedFilesParams,
+    refresh_diagnostics: bool,
+) -> Result<()> {
+    let is_module_file = |path: &Path| {
+        modules::module_files().iter().any(|file| {
+            fs::canonicalize(file).is_ok_and(|file| file == path) || Path::new(file) == path
+        })
+    };
+    if !params.changes.iter().any(|change| {
+        change
+            .uri
+            .to_file_path()
+            .is_ok_and(|path| is_module_file(&path))
+    }) {
+        return Ok(());
+    }
+    if let Err(err) = modules::reload_module_mappings() {
+        // the previous mappings stay in place until the files are valid again.
+        log::error!("failed to reload module mappings: {}", err);
+        return Ok(());
+    }
+    if !refresh_diagnostics {
+        return Ok(());
+    }
+    // diagnostics are pulled by the client, so it has to be asked to do so again.
+    return send_request(
+        connection,
+        "workspace/diagnostic/refresh",
+        serde_json::Value::Null,
+    );
+}
+
 fn changed(params: DidChangeTextDocumentParams) -> Result<()> {
     let uri = params.text_document.uri;
     return match &params.content_changes.last() {
-        Some(change) => document_store::Document::new(change.text.to_owned()).map(|document| {
-            document_store::put(&uri, document);
+        Some(change) => document_store::update(&uri, change.text.to_owned()).map(|_| {
             log::debug!("updated {}", uri);
         }),
         None => Ok(()),
@@ -150,8 +371,15 @@ fn opened(params: DidOpenTextDocumentParams) -> Result<()> {
 
 fn saved(params: DidSaveTextDocumentParams) -> Result<()> {
     let uri = params.text_document.uri;
-    return document_store::Document::from_uri(&uri).map(|document| {
-        document_store::put(&uri, document);
+    return match params.text {
+        Some(text) => document_store::update(&uri, text),
+        None => uri
+            .to_file_path()
+            .map_err(|_| anyhow::anyhow!("failed to read file path from uri {}", uri))
+            .and_then(|path| fs::read_to_string(path).map_err(anyhow::Error::from))
+            .and_then(|text| document_store::update(&uri, text)),
+    }
+    .map(|_| {
         log::debug!("saved {}", uri);
     });
 }
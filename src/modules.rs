@@ -27,25 +27,81 @@ impl Deref for ModuleMappings {
 
 pub(crate) static MODULE_MAPPINGS: OnceLock<Arc<Mutex<ModuleMappings>>> = OnceLock::new();
 
-pub(crate) fn init_module_mappings_from_file(file: &str) -> Result<()> {
+static MODULE_FILES: OnceLock<Vec<String>> = OnceLock::new();
+
+/**
+ * merges all given module files. on name collisions modules of later files override earlier ones.
+ */
+pub(crate) fn init_module_mappings_from_files(files: &[String]) -> Result<()> {
+    MODULE_FILES
+        .set(files.to_vec())
+        .map_err(|_| anyhow::anyhow!("could not initialize module files; already initialized"))?;
     MODULE_MAPPINGS
-        .set(Arc::new(Mutex::new(ModuleMappings(
-            fs::read_to_string(&file)
-                .map_err(|err| anyhow::anyhow!("failed to read {}: {}", file, err))
-                .and_then(|text| {
-                    serde_json::from_str(&text)
-                        .map_err(|err| anyhow::anyhow!("could not parse json in {}: {}", file, err))
-                })?,
-        ))))
+        .set(Arc::new(Mutex::new(read_module_files(files)?)))
         .map_err(|_| anyhow::anyhow!("could not initialize module mappings; mutex poisoned"))?;
     log::info!(
-        "created module mappings from {}: {:?}",
-        file,
+        "created module mappings from {:?}: {:?}",
+        files,
         MODULE_MAPPINGS.get().unwrap()
     );
     return Ok(());
 }
 
+/**
+ * the module files given on the commandline.
+ */
+pub(crate) fn module_files() -> &'static [String] {
+    return MODULE_FILES.get().map(Vec::as_slice).unwrap_or(&[]);
+}
+
+/**
+ * re-reads all module files, replacing the current module mappings.
+ */
+pub(crate) fn reload_module_mappings() -> Result<()> {
+    return reload_module_mappings_into(
+        MODULE_MAPPINGS
+            .get()
+            .expect("module mappings not initialized"),
+        module_files(),
+    );
+}
+
+fn reload_module_mappings_into(mappings: &Mutex<ModuleMappings>, files: &[String]) -> Result<()> {
+    let reloaded = read_module_files(files)?;
+    let mut mappings = mappings.lock().expect("module mappings mutex poisoned");
+    *mappings = reloaded;
+    log::info!("reloaded module mappings from {:?}: {:?}", files, mappings);
+    return Ok(());
+}
+
+fn read_module_files(files: &[String]) -> Result<ModuleMappings> {
+    let mut modules: HashMap<String, Module> = HashMap::new();
+    for file in files {
+        let mappings: HashMap<String, Module> = fs::read_to_string(&file)
+            .map_err(|err| anyhow::anyhow!("failed to read {}: {}", file, err))
+            .and_then(|text| {
+                serde_json::from_str(&text)
+                    .map_err(|err| anyhow::anyhow!("could not parse json in {}: {}", file, err))
+            })?;
+        for (name, module) in mappings {
+            if let Some(previous) = modules
+                .get(&name)
+                .filter(|previous| previous.path != module.path)
+            {
+                log::warn!(
+                    "module \"{}\" at {} is overridden by {} from {}",
+                    name,
+                    previous.path,
+                    module.path,
+                    file
+                );
+            }
+            modules.insert(name, module);
+        }
+    }
+    return Ok(ModuleMappings(modules));
+}
+
 pub(crate) fn init_empty_module_mappings() -> Result<()> {
     MODULE_MAPPINGS
         .set(Arc::new(Mutex::new(ModuleMappings(HashMap::new()))))
@@ -87,3 +143,84 @@ pub(crate) fn find_module_for_file(file: &Path) -> Option<Module> {
         .iter()
         .find_map(|(_, module)| file.strip_prefix(&module.path).ok().map(|_| module.clone()));
 }
+
+/**
+ * the module referenced by a `module` attribute. if it is missing or `${module.id}` the module
+ * of the file itself is used.
+ */
+pub(crate) fn resolve_module(module: Option<&str>, file: &Path) -> Option<Module> {
+    return match module {
+        Some("${module.id}") | None => find_module_for_file(file),
+        Some(module) => find_module_by_name(module),
+    };
+}
+
+#[cfg(test)]
+pub(crate) fn insert_module(name: &str, module: Module) {
+    MODULE_MAPPINGS
+        .get_or_init(|| Arc::new(Mutex::new(ModuleMappings(HashMap::new()))))
+        .lock()
+        .expect("module mappings mutex poisoned")
+        .0
+        .insert(name.to_string(), module);
+}
+
+#[cfg(test)]
+pub(crate) fn remove_module(name: &str) {
+    if let Some(mappings) = MODULE_MAPPINGS.get() {
+        mappings
+            .lock()
+            .expect("module mappings mutex poisoned")
+            .0
+            .remove(name);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, fs, sync::Mutex};
+
+    use crate::document_store::TestDirectory;
+
+    use super::{read_module_files, reload_module_mappings_into, ModuleMappings};
+
+    #[test]
+    fn test_later_module_files_override_earlier_ones() {
+        let test_directory = TestDirectory::new("module-files");
+        let directory = &test_directory.path;
+        let first = directory.join("first.json");
+        let second = directory.join("second.json");
+        fs::write(
+            &first,
+            r#"{"shared": {"path": "/first/shared"}, "first": {"path": "/first/only"}}"#,
+        )
+        .unwrap();
+        fs::write(&second, r#"{"shared": {"path": "/second/shared"}}"#).unwrap();
+        let modules = read_module_files(&[
+            first.to_string_lossy().to_string(),
+            second.to_string_lossy().to_string(),
+        ])
+        .unwrap();
+        assert_eq!(modules.get("shared").unwrap().path, "/second/shared");
+        assert_eq!(modules.get("first").unwrap().path, "/first/only");
+    }
+
+    #[test]
+    fn test_reload_picks_up_new_modules() {
+        let test_directory = TestDirectory::new("module-reload");
+        let directory = &test_directory.path;
+        let file = directory.join("modules.json");
+        let files = vec![file.to_string_lossy().to_string()];
+        fs::write(&file, r#"{"old": {"path": "/old"}}"#).unwrap();
+        let mappings = Mutex::new(ModuleMappings(HashMap::new()));
+        reload_module_mappings_into(&mappings, &files).unwrap();
+        assert!(mappings.lock().unwrap().get("new").is_none());
+        fs::write(
+            &file,
+            r#"{"old": {"path": "/old"}, "new": {"path": "/new"}}"#,
+        )
+        .unwrap();
+        reload_module_mappings_into(&mappings, &files).unwrap();
+        assert_eq!(mappings.lock().unwrap().get("new").unwrap().path, "/new");
+    }
+}
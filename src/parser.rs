@@ -5,17 +5,30 @@ pub(crate) fn find_current_node<'tree>(
     tree: &'tree Tree,
     position: Position,
 ) -> Option<Node<'tree>> {
+    let node = find_node_path(tree, position).pop();
+    log::debug!("current node: {:?}", node);
+    return node;
+}
+
+/**
+ * all nodes surrounding the given position, from the root to the innermost one.
+ */
+pub(crate) fn find_node_path<'tree>(tree: &'tree Tree, position: Position) -> Vec<Node<'tree>> {
     let trigger_point = Point::new(position.line as usize, position.character as usize);
     let mut cursor = tree.root_node().walk();
+    let mut path = vec![cursor.node()];
     loop {
-        let node = cursor.node();
-        if match node.end_position() <= trigger_point {
-            true => !cursor.goto_next_sibling() || cursor.node().start_position() > trigger_point,
-            false => !cursor.goto_first_child(),
+        let descend = cursor.node().end_position() > trigger_point;
+        if !match descend {
+            true => cursor.goto_first_child(),
+            false => cursor.goto_next_sibling() && cursor.node().start_position() <= trigger_point,
         } {
-            log::debug!("current node: {:?}", node);
-            return Some(node);
+            return path;
+        }
+        if !descend {
+            path.pop();
         }
+        path.push(cursor.node());
     }
 }
 
@@ -28,8 +41,8 @@ pub(crate) fn attribute_name_of<'a>(attribute: Node<'_>, source: &'a str) -> Opt
 pub(crate) fn attribute_value_of<'a>(attribute: Node<'_>, source: &'a str) -> Option<&'a str> {
     return attribute
         .child(2)
-        .and_then(|node| node.child(1))
-        .and_then(|node| node.utf8_text(source.as_bytes()).ok());
+        .and_then(|node| string_content_of(node, source))
+        .map(|(_, content)| content);
 }
 
 pub(crate) fn attribute_name_and_value_of<'a>(
@@ -44,10 +57,144 @@ pub(crate) fn attribute_name_and_value_of<'a>(
                 name,
                 attribute
                     .child(2)
-                    .and_then(|node| node.child(1))
-                    .filter(|node| node.kind() == "string_content")
-                    .and_then(|node| node.utf8_text(source.as_bytes()).ok())
+                    .and_then(|node| string_content_of(node, source))
+                    .map(|(_, content)| content)
                     .unwrap_or(""),
             )
         });
 }
+
+/**
+ * the raw content between the quotes of a string node and where it starts. the content may be
+ * split into multiple `string_content` and `xml_entity` nodes, which are not decoded.
+ */
+pub(crate) fn string_content_of<'a>(string: Node<'_>, source: &'a str) -> Option<(Point, &'a str)> {
+    if string.child_count() < 2 {
+        return None;
+    }
+    let opening = string.child(0)?;
+    let closing = string.child(string.child_count() - 1)?;
+    return source
+        .get(opening.end_byte()..closing.start_byte())
+        .map(|content| (opening.end_position(), content));
+}
+
+/**
+ * the spml grammar does not know about cdata sections, so they end up as error nodes. these should
+ * be treated as opaque text.
+ */
+pub(crate) fn is_cdata(node: Node<'_>, source: &str) -> bool {
+    return node.is_error()
+        && node
+            .utf8_text(source.as_bytes())
+            .is_ok_and(|text| text.starts_with("<![CDATA["));
+}
+
+/**
+ * the grammar parses java declarations `<%! ... %>` and scriptlets `<% ... %>` both as `java_tag`,
+ * so they can only be told apart by their text.
+ */
+pub(crate) fn is_java_declaration(node: Node<'_>, source: &str) -> bool {
+    return node.kind() == "java_tag"
+        && node
+            .utf8_text(source.as_bytes())
+            .is_ok_and(|text| text.starts_with("<%!"));
+}
+
+#[cfg(test)]
+mod tests {
+    use lsp_types::Position;
+
+    use crate::document_store::test_document;
+
+    use super::{attribute_value_of, find_node_path, is_cdata, is_java_declaration};
+
+    #[test]
+    fn test_attribute_value_with_xml_entity() {
+        let document = test_document("<sp:set name=\"_x\" value=\"a&amp;b\"/>\n");
+        let text = document.text.as_str();
+        let tree = &document.tree;
+        let tag = tree
+            .root_node()
+            .children(&mut tree.root_node().walk())
+            .find(|node| node.kind() == "set_tag")
+            .unwrap();
+        let attribute = tag
+            .children(&mut tag.walk())
+            .find(|node| node.kind() == "value_attribute")
+            .unwrap();
+        assert_eq!(attribute_value_of(attribute, text), Some("a&amp;b"));
+        let string = attribute.child(2).unwrap();
+        let closing = string.child(string.child_count() - 1).unwrap();
+        assert_eq!(closing.start_position().row, 3);
+        assert_eq!(closing.start_position().column, 32);
+    }
+
+    #[test]
+    fn test_node_path_in_nested_body() {
+        let document = test_document(concat!(
+            "<sp:if name=\"_a\" isNull=\"true\">\n",
+            "\t<div>\n",
+            "\t\t<sp:print name=\"_b\"/>\n",
+            "\t</div>\n",
+            "</sp:if>\n",
+        ));
+        let kinds: Vec<&str> = find_node_path(&document.tree, Position::new(5, 19))
+            .iter()
+            .filter(|node| node.is_named())
+            .map(|node| node.kind())
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                "document",
+                "if_tag",
+                "html_tag",
+                "print_tag",
+                "name_attribute",
+                "string",
+                "string_content",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_cdata_is_an_error_node() {
+        let document = test_document(concat!(
+            "<div>\n",
+            "\t<![CDATA[ if (a < b) { } ]]>\n",
+            "</div>\n",
+        ));
+        let text = document.text.as_str();
+        let tree = &document.tree;
+        let mut cdata = Vec::new();
+        let mut nodes = vec![tree.root_node()];
+        while let Some(node) = nodes.pop() {
+            if is_cdata(node, text) {
+                cdata.push(node);
+            }
+            nodes.extend(node.children(&mut node.walk()));
+        }
+        assert_eq!(cdata.len(), 1);
+        assert_eq!(cdata[0].kind(), "ERROR");
+        assert_eq!(cdata[0].start_position().row, 4);
+        assert_eq!(cdata[0].start_position().column, 1);
+    }
+
+    #[test]
+    fn test_java_declaration_and_scriptlet() {
+        let document = test_document(concat!(
+            "<%! private int counter = 0; %>\n",
+            "<% counter++; %>\n",
+        ));
+        let text = document.text.as_str();
+        let tree = &document.tree;
+        let declarations: Vec<bool> = tree
+            .root_node()
+            .children(&mut tree.root_node().walk())
+            .filter(|node| node.kind() == "java_tag")
+            .map(|node| is_java_declaration(node, text))
+            .collect();
+        assert_eq!(declarations, vec![true, false]);
+    }
+}
@@ -0,0 +1,250 @@
+use super::ast::{
+    Argument, Comparable, Condition, Expression, Function, Identifier, Interpolation, Object,
+    SpelAst, SpelResult, StringLiteral, Uri, Word, WordFragment,
+};
+
+/**
+ * prints a parsed spel attribute value in its canonical form: with single spaces around operators,
+ * ", " between function arguments and no other whitespace. formatting the result again does not
+ * change it anymore.
+ * invalid values as well as queries and regexes, whose content is not part of the ast, cannot be
+ * formatted.
+ */
+pub(crate) fn format(ast: &SpelAst) -> Option<String> {
+    return match ast {
+        SpelAst::Comparable(SpelResult::Valid(comparable)) => Some(format_comparable(comparable)),
+        SpelAst::Condition(SpelResult::Valid(condition)) => Some(format_condition(condition)),
+        SpelAst::Expression(SpelResult::Valid(expression)) => Some(format_expression(expression)),
+        SpelAst::Identifier(SpelResult::Valid(identifier)) => Some(format_identifier(identifier)),
+        SpelAst::Object(SpelResult::Valid(object)) => Some(format_object(object)),
+        SpelAst::String(SpelResult::Valid(word)) => Some(format_word(word)),
+        SpelAst::Uri(SpelResult::Valid(uri)) => Some(format_uri(uri)),
+        _ => None,
+    };
+}
+
+fn format_object(object: &Object) -> String {
+    return match object {
+        Object::Anchor(anchor) => format!("!{{{}}}", format_word(&anchor.name)),
+        Object::Function(function) => format_function(function),
+        Object::Name(name) => format_word(name),
+        Object::String(string) => format_string(string),
+        Object::FieldAccess { object, field, .. } => {
+            format!("{}.{}", format_object(object), format_word(field))
+        }
+        Object::MethodAccess {
+            object, function, ..
+        } => format!("{}.{}", format_object(object), format_function(function)),
+        Object::ArrayAccess { object, index, .. } => {
+            format!("{}[{}]", format_object(object), format_expression(index))
+        }
+    };
+}
+
+fn format_expression(expression: &Expression) -> String {
+    return match expression {
+        Expression::Function(function) => format_function(function),
+        Expression::Null(null) => null.to_string(),
+        Expression::Number(number) => number.to_string(),
+        Expression::Object(interpolation) => format_interpolation(interpolation),
+        Expression::SignedExpression {
+            expression, sign, ..
+        } => format!("{}{}", sign, format_expression(expression)),
+        Expression::BracketedExpression { expression, .. } => {
+            format!("({})", format_expression(expression))
+        }
+        Expression::BinaryOperation {
+            left,
+            operator,
+            right,
+            ..
+        } => format!(
+            "{} {} {}",
+            format_expression(left),
+            operator,
+            format_expression(right)
+        ),
+        Expression::Ternary {
+            condition,
+            left,
+            right,
+            ..
+        } => format!(
+            "{} ? {} : {}",
+            format_condition(condition),
+            format_expression(left),
+            format_expression(right)
+        ),
+    };
+}
+
+fn format_condition(condition: &Condition) -> String {
+    return match condition {
+        Condition::True { .. } => "true".to_string(),
+        Condition::False { .. } => "false".to_string(),
+        Condition::Object(interpolation) => format_interpolation(interpolation),
+        Condition::Function(function) => format_function(function),
+        Condition::BinaryOperation {
+            left,
+            operator,
+            right,
+            ..
+        } => format!(
+            "{} {} {}",
+            format_condition(left),
+            operator,
+            format_condition(right)
+        ),
+        Condition::BracketedCondition { condition, .. } => {
+            format!("({})", format_condition(condition))
+        }
+        Condition::NegatedCondition { condition, .. } => {
+            format!("!{}", format_condition(condition))
+        }
+        Condition::Comparisson {
+            left,
+            operator,
+            right,
+            ..
+        } => format!(
+            "{} {} {}",
+            format_comparable(left),
+            operator,
+            format_comparable(right)
+        ),
+    };
+}
+
+fn format_comparable(comparable: &Comparable) -> String {
+    return match comparable {
+        Comparable::Condition(condition) => format_condition(condition),
+        Comparable::Expression(expression) => format_expression(expression),
+        Comparable::Function(function) => format_function(function),
+        Comparable::Object(interpolation) => format_interpolation(interpolation),
+        Comparable::String(string) => format_string(string),
+        Comparable::Null(null) => null.to_string(),
+    };
+}
+
+fn format_identifier(identifier: &Identifier) -> String {
+    return match identifier {
+        Identifier::Name(name) => format_word(name),
+        Identifier::FieldAccess {
+            identifier, field, ..
+        } => format!("{}.{}", format_identifier(identifier), format_word(field)),
+    };
+}
+
+fn format_uri(uri: &Uri) -> String {
+    return match uri {
+        Uri::Literal(literal) => {
+            let mut result = String::new();
+            for fragment in &literal.fragments {
+                result.push('/');
+                result.push_str(&format_word(&fragment.content));
+            }
+            if let Some(extension) = &literal.file_extension {
+                result.push('.');
+                result.push_str(&format_word(&extension.content));
+            }
+            result
+        }
+        Uri::Object(interpolation) => format_interpolation(interpolation),
+    };
+}
+
+fn format_function(function: &Function) -> String {
+    let arguments: Vec<String> = function
+        .arguments
+        .iter()
+        .map(|argument| format_argument(&argument.argument))
+        .collect();
+    return format!("{}({})", function.name, arguments.join(", "));
+}
+
+fn format_argument(argument: &Argument) -> String {
+    return match argument {
+        Argument::Anchor(anchor) => format!("!{{{}}}", format_word(&anchor.name)),
+        Argument::Function(function) => format_function(function),
+        Argument::Null(null) => null.to_string(),
+        Argument::Number(number) => number.to_string(),
+        Argument::Object(interpolation) => format_interpolation(interpolation),
+        Argument::SignedNumber(number) => number.to_string(),
+        Argument::String(string) => format_string(string),
+        Argument::True { .. } => "true".to_string(),
+        Argument::False { .. } => "false".to_string(),
+    };
+}
+
+fn format_word(word: &Word) -> String {
+    return word
+        .fragments
+        .iter()
+        .map(|fragment| match fragment {
+            WordFragment::String(string) => string.content.clone(),
+            WordFragment::Interpolation(interpolation) => format_interpolation(interpolation),
+        })
+        .collect();
+}
+
+fn format_interpolation(interpolation: &Interpolation) -> String {
+    return format!("${{{}}}", format_object(&interpolation.content));
+}
+
+/**
+ * string literals keep their content, including escape sequences, but lose their quotes while
+ * parsing.
+ */
+fn format_string(string: &StringLiteral) -> String {
+    return format!("'{}'", string.content);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::spel::{
+        ast::{SpelAst, SpelResult},
+        parser::Parser,
+    };
+
+    use super::format;
+
+    #[test]
+    fn test_format_condition() {
+        assert_eq!(
+            format_condition("(false||  true)&&${test}=='a b'"),
+            "(false || true) && ${test} == 'a b'"
+        );
+    }
+
+    #[test]
+    fn test_format_object() {
+        assert_eq!(
+            format_object("\tis_string (\t'test'  , concat('a',${_b}) ) "),
+            "is_string('test', concat('a', ${_b}))"
+        );
+        assert_eq!(format_object("_strings[ 1+2 ]"), "_strings[1 + 2]");
+    }
+
+    #[test]
+    fn test_format_is_stable() {
+        for condition in [
+            "(false || true) && ${test} == 'a b'",
+            "3 >= 4 && !(${_a} != null)",
+        ] {
+            assert_eq!(format_condition(condition), condition);
+        }
+        for object in ["is_string('te\\'st', -1)", "!{home-${_object}-content}"] {
+            assert_eq!(format_object(object), object);
+        }
+    }
+
+    fn format_condition(string: &str) -> String {
+        let condition = Parser::new(string).parse_condition_ast().unwrap().root;
+        return format(&SpelAst::Condition(SpelResult::Valid(condition))).unwrap();
+    }
+
+    fn format_object(string: &str) -> String {
+        let object = Parser::new(string).parse_object_ast().unwrap().root;
+        return format(&SpelAst::Object(SpelResult::Valid(object))).unwrap();
+    }
+}
@@ -1,4 +1,5 @@
 pub(crate) mod ast;
+pub(crate) mod format;
 pub(crate) mod grammar;
 pub(crate) mod parser;
 
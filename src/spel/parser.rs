@@ -257,28 +257,59 @@ impl Parser {
         }
         let start = self.scanner.cursor as u16;
         let mut length = 0;
-        loop {
-            match self.scanner.peek() {
-                // TODO: ACTUALLY parse the regex
-                // Some('[') => todo!(),
-                // Some('(') => todo!(),
-                // Some('\\') => todo!(),
-                // ...
-                Some(_char) => {
-                    length += 1;
-                    self.scanner.pop();
-                }
-                None => {
-                    return Ok(Regex {
-                        location: Location::VariableLength {
-                            char: start,
-                            line: 0,
-                            length,
-                        },
-                    })
+        let mut groups = 0;
+        // character classes may be nested, like in "[a-z&&[^b]]"
+        let mut classes = 0;
+        // only the structure is checked, quantifiers and the like are not validated (yet)
+        while let Some(char) = self.scanner.pop() {
+            length += 1;
+            match char {
+                '\\' => match self.scanner.pop() {
+                    Some(_) => length += 1,
+                    None => return Err(syntax_error!("unescaped \"\\\" at the end of the regex")),
+                },
+                '[' => classes += 1,
+                ']' if classes > 0 => classes -= 1,
+                '(' if classes == 0 => groups += 1,
+                ')' if classes == 0 && groups == 0 => {
+                    return Err(SyntaxError {
+                        message: "unmatched closing bracket. try escaping it".to_string(),
+                        proposed_fixes: vec![SyntaxFix::Insert(
+                            Position {
+                                line: 0,
+                                character: self.scanner.cursor as u32 - 1,
+                            },
+                            "\\".to_string(),
+                        )],
+                    });
                 }
+                ')' if classes == 0 => groups -= 1,
+                _ => {}
             }
         }
+        let end = Position {
+            line: 0,
+            character: self.scanner.cursor as u32,
+        };
+        if classes > 0 {
+            return Err(SyntaxError {
+                message: "unclosed character class. try adding \"]\"".to_string(),
+                proposed_fixes: vec![SyntaxFix::Insert(end, "]".repeat(classes))],
+            });
+        }
+        if groups > 0 {
+            return Err(SyntaxError {
+                message: "unclosed group. try adding \")\"".to_string(),
+                proposed_fixes: vec![SyntaxFix::Insert(end, ")".repeat(groups))],
+            });
+        }
+        return Ok(Regex {
+            location: Location::VariableLength {
+                char: start,
+                line: 0,
+                length,
+            },
+        });
     }
 
     pub(crate) fn parse_query(&mut self) -> Result<Query, SyntaxError> {
@@ -1644,7 +1675,7 @@ mod tests {
     use crate::spel::ast::{
         Anchor, Argument, Comparable, ComparissonOperator, Condition, ConditionAst,
         ConditionOperator, Expression, ExpressionAst, ExpressionOperator, Function,
-        FunctionArgument, Interpolation, Location, Null, Number, Object, ObjectAst, Sign,
+        FunctionArgument, Interpolation, Location, Null, Number, Object, ObjectAst, Regex, Sign,
         StringLiteral, Word, WordFragment,
     };
 
@@ -2781,6 +2812,48 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_regex() {
+        assert_eq!(
+            super::Parser::new("[a-z]+(\\d|[\\]()])?")
+                .parse_regex()
+                .ok(),
+            Some(Regex {
+                location: Location::VariableLength {
+                    char: 0,
+                    line: 0,
+                    length: 18,
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid_regex() {
+        let message = |regex: &str| {
+            super::Parser::new(regex)
+                .parse_regex()
+                .map_err(|err| err.message)
+                .err()
+        };
+        assert_eq!(
+            message("[a-z"),
+            Some("unclosed character class. try adding \"]\"".to_string())
+        );
+        assert_eq!(
+            message("(a|b"),
+            Some("unclosed group. try adding \")\"".to_string())
+        );
+        assert_eq!(
+            message("a)"),
+            Some("unmatched closing bracket. try escaping it".to_string())
+        );
+        assert_eq!(
+            message("a\\"),
+            Some("unescaped \"\\\" at the end of the regex".to_string())
+        );
+    }
+
     fn parse_object(string: &str) -> ObjectAst {
         return (&mut super::Parser::new(&string))
             .parse_object_ast()